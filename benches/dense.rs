@@ -0,0 +1,57 @@
+//! Run with `cargo bench --bench dense`, and again with `--features rayon` to compare the parallel word loops.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use meshvox::BitGrid;
+
+/// A 512³ grid whose words follow a fixed xorshift sequence.
+fn random_grid(seed: u64) -> BitGrid {
+    let dims = [512, 512, 512];
+    let mut state = seed;
+    let words = (0..dims[0] * dims[1] * dims[2] / 64)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect();
+    BitGrid {
+        words,
+        dims,
+        origin: [0, 0, 0],
+    }
+}
+
+fn boolean_ops(c: &mut Criterion) {
+    let a = random_grid(0x9e37_79b9_7f4a_7c15);
+    let b = random_grid(0xbf58_476d_1ce4_e5b9);
+    let mut group = c.benchmark_group("bitgrid_512");
+    group.bench_function("union_with", |bench| {
+        bench.iter_batched_ref(
+            || a.clone(),
+            |grid| grid.union_with(black_box(&b)),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("intersect_with", |bench| {
+        bench.iter_batched_ref(
+            || a.clone(),
+            |grid| grid.intersect_with(black_box(&b)),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("difference_with", |bench| {
+        bench.iter_batched_ref(
+            || a.clone(),
+            |grid| grid.difference_with(black_box(&b)),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("count_ones", |bench| {
+        bench.iter(|| black_box(&a).count_ones())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, boolean_ops);
+criterion_main!(benches);
@@ -0,0 +1,175 @@
+//! Run with `cargo bench --bench voxelize`, and again with `--features rayon` to compare the parallel scan.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::load_obj;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use meshvox::{triangle_aabb_intersects, Triangle, Vector3, VoxelizeConfig, Voxels, AABB};
+use std::collections::HashSet;
+
+/// Large triangles at random orientations, from a fixed linear congruential sequence.
+fn oblique_triangles(count: usize) -> (Vec<[f64; 3]>, Vec<usize>) {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+    };
+    let vertices = (0..count * 3)
+        .map(|_| [next(), next(), next()])
+        .collect::<Vec<_>>();
+    (vertices, (0..count * 3).collect())
+}
+
+/// A few triangles spanning the whole box, among many much smaller than a cell.
+fn skewed_triangles() -> (Vec<[f64; 3]>, Vec<usize>) {
+    let (mut vertices, _) = oblique_triangles(4);
+    let (small, _) = oblique_triangles(3000);
+    vertices.extend(
+        small
+            .iter()
+            .map(|v| [v[0] * 0.01, v[1] * 0.01, v[2] * 0.01]),
+    );
+    let len = vertices.len();
+    (vertices, (0..len).collect())
+}
+
+fn quick_reject(c: &mut Criterion) {
+    // Every cell of each triangle's range plus a margin of one cell, most of which the triangle misses.
+    let (vertices, indices) = load_obj("torus");
+    let step = 0.05;
+    let tris = indices
+        .chunks(3)
+        .map(|t| {
+            let p = |i: usize| Vector3::new(vertices[i][0], vertices[i][1], vertices[i][2]);
+            Triangle::new(&p(t[0]), &p(t[1]), &p(t[2]))
+        })
+        .collect::<Vec<_>>();
+    c.bench_function("triangle_aabb_intersects/torus", |b| {
+        b.iter(|| {
+            let mut hits = 0;
+            for tri in tris.iter() {
+                let min = tri.aabb().min;
+                let max = tri.aabb().max;
+                let lo = [min.x, min.y, min.z].map(|v| (v / step).floor() as i32 - 1);
+                let hi = [max.x, max.y, max.z].map(|v| (v / step).floor() as i32 + 1);
+                for x in lo[0]..=hi[0] {
+                    for y in lo[1]..=hi[1] {
+                        for z in lo[2]..=hi[2] {
+                            let cell_min = Vector3::new(x as f64, y as f64, z as f64) * step;
+                            let cell = AABB {
+                                min: cell_min,
+                                max: cell_min + Vector3::new(step, step, step),
+                            };
+                            if triangle_aabb_intersects(tri, &cell) {
+                                hits += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            black_box(hits)
+        })
+    });
+}
+
+/// Voxelizes by testing every cell of each triangle's range, the scan `voxelize` used before the span scan.
+fn voxelize_every_cell(vertices: &[[f64; 3]], indices: &[usize], step: f64) -> HashSet<[i32; 3]> {
+    let eps = f64::EPSILON * 10.0;
+    let mut cells = HashSet::new();
+    for t in indices.chunks(3) {
+        let p = |i: usize| Vector3::new(vertices[i][0], vertices[i][1], vertices[i][2]);
+        let tri = Triangle::new(&p(t[0]), &p(t[1]), &p(t[2]));
+        let min = tri.aabb().min;
+        let max = tri.aabb().max;
+        let lo = [min.x, min.y, min.z].map(|v| (v / step).floor() as i32);
+        let hi = [max.x, max.y, max.z].map(|v| (v / step).ceil() as i32);
+        for x in lo[0]..=hi[0] {
+            for y in lo[1]..=hi[1] {
+                for z in lo[2]..=hi[2] {
+                    let cell_min = Vector3::new(x as f64, y as f64, z as f64) * step;
+                    let cell = AABB {
+                        min: cell_min - Vector3::new(eps, eps, eps),
+                        max: cell_min + Vector3::new(step + eps, step + eps, step + eps),
+                    };
+                    if triangle_aabb_intersects(&tri, &cell) {
+                        cells.insert([x, y, z]);
+                    }
+                }
+            }
+        }
+    }
+    cells
+}
+
+fn scan(c: &mut Criterion) {
+    let (vertices, indices) = oblique_triangles(20);
+    let step = 0.01;
+    assert_eq!(
+        Voxels::voxelize(&vertices, &indices, step).grid_positions,
+        voxelize_every_cell(&vertices, &indices, step)
+    );
+    let mut group = c.benchmark_group("scan/oblique");
+    group.sample_size(10);
+    group.bench_function("span", |b| {
+        b.iter(|| Voxels::voxelize(black_box(&vertices), &indices, step))
+    });
+    group.bench_function("every_cell", |b| {
+        b.iter(|| voxelize_every_cell(black_box(&vertices), &indices, step))
+    });
+    group.finish();
+}
+
+fn tiles(c: &mut Criterion) {
+    let (vertices, indices) = skewed_triangles();
+    let step = 0.01;
+    let mut group = c.benchmark_group("tiles/skewed");
+    group.sample_size(10);
+    for tile_size in [None, Some(8), Some(32)].iter() {
+        let config = VoxelizeConfig {
+            tile_size: *tile_size,
+            ..VoxelizeConfig::default()
+        };
+        let name = match tile_size {
+            Some(size) => format!("tile_{}", size),
+            None => "untiled".to_string(),
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| Voxels::voxelize_with_config(black_box(&vertices), &indices, step, &config))
+        });
+    }
+    group.finish();
+}
+
+fn solid(c: &mut Criterion) {
+    let (vertices, indices) = load_obj("torus");
+    let step = 0.02;
+    let mut group = c.benchmark_group("solid/torus");
+    group.sample_size(10);
+    group.bench_function("voxelize_solid", |b| {
+        b.iter(|| Voxels::voxelize_solid(black_box(&vertices), &indices, step))
+    });
+    group.bench_function("voxelize_then_fill", |b| {
+        b.iter(|| {
+            let mut voxels = Voxels::voxelize(black_box(&vertices), &indices, step);
+            voxels.fill();
+            voxels
+        })
+    });
+    group.finish();
+}
+
+fn surface(c: &mut Criterion) {
+    let (vertices, indices) = load_obj("torus");
+    let mut group = c.benchmark_group("voxelize/torus");
+    group.sample_size(10);
+    group.bench_function("step_0.01", |b| {
+        b.iter(|| Voxels::voxelize(black_box(&vertices), &indices, 0.01))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, quick_reject, scan, tiles, solid, surface);
+criterion_main!(benches);
@@ -0,0 +1,159 @@
+use super::voxelize::{assert_input, build_triangles, default_eps, Voxels};
+use num_traits::Float;
+use std::collections::{HashMap, HashSet};
+
+/// Cosine of the largest angle between triangle normals that still counts as flat within one coarse cell.
+const FLAT_COS: f64 = 0.985; // about 10 degrees
+
+/// A two-level voxelization: coarse cells for flat regions and fine cells where the surface has detail.
+pub struct AdaptiveVoxels<T: Float> {
+    /// Coarse cells that were not refined.
+    pub coarse: Voxels<T>,
+    /// Fine cells covering the refined coarse cells.
+    pub fine: Voxels<T>,
+    /// Coarse cells that were replaced by fine cells.
+    pub refined: HashSet<[i32; 3]>,
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes at `coarse_step`, then re-voxelizes the detailed coarse cells at `fine_step`.
+    ///
+    /// A coarse cell counts as detailed when the triangles touching it are not coplanar,
+    /// i.e. their normals differ by more than about 10 degrees. Flat areas stay coarse however finely they are tessellated.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the input `try_voxelize` rejects at either step, except that an empty mesh gives no voxels.
+    pub fn voxelize_adaptive(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        coarse_step: T,
+        fine_step: T,
+    ) -> AdaptiveVoxels<T> {
+        assert_input(vertices, indices, coarse_step);
+        assert_input(vertices, indices, fine_step);
+        let eps = default_eps();
+        let tris = build_triangles(vertices, indices);
+        let normals = tris
+            .iter()
+            .map(|tri| {
+                let n = (tri.points[1] - tri.points[0]).cross(&(tri.points[2] - tri.points[0]));
+                let len = n.dot(&n).sqrt();
+                if len > T::zero() {
+                    n / len
+                } else {
+                    n
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut cell_tris: HashMap<[i32; 3], Vec<usize>> = HashMap::new();
+        for (i, tri) in tris.iter().enumerate() {
+            for cell in tri.voxelize(coarse_step, eps) {
+                cell_tris.entry(cell).or_default().push(i);
+            }
+        }
+
+        let flat_cos = T::from(FLAT_COS).unwrap();
+        let mut coarse = HashSet::new();
+        let mut refined = HashSet::new();
+        for (cell, tri_indices) in cell_tris.iter() {
+            let first = normals[tri_indices[0]];
+            if tri_indices
+                .iter()
+                .all(|i| normals[*i].dot(&first) >= flat_cos)
+            {
+                coarse.insert(*cell);
+            } else {
+                refined.insert(*cell);
+            }
+        }
+
+        let half = T::from(0.5).unwrap();
+        let mut fine = HashSet::new();
+        let mut refined_tris = refined
+            .iter()
+            .flat_map(|cell| cell_tris[cell].iter().cloned())
+            .collect::<Vec<_>>();
+        refined_tris.sort_unstable();
+        refined_tris.dedup();
+        for i in refined_tris {
+            for cell in tris[i].voxelize(fine_step, eps) {
+                let parent = [
+                    ((T::from(cell[0]).unwrap() + half) * fine_step / coarse_step)
+                        .floor()
+                        .to_i32()
+                        .unwrap(),
+                    ((T::from(cell[1]).unwrap() + half) * fine_step / coarse_step)
+                        .floor()
+                        .to_i32()
+                        .unwrap(),
+                    ((T::from(cell[2]).unwrap() + half) * fine_step / coarse_step)
+                        .floor()
+                        .to_i32()
+                        .unwrap(),
+                ];
+                if refined.contains(&parent) {
+                    fine.insert(cell);
+                }
+            }
+        }
+
+        AdaptiveVoxels {
+            coarse: Voxels {
+                grid_positions: coarse,
+                step: coarse_step,
+            },
+            fine: Voxels {
+                grid_positions: fine,
+                step: fine_step,
+            },
+            refined,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::pyramid;
+
+    #[test]
+    fn fine_cells_appear_only_around_the_detail() {
+        // A flat square with a small pyramid standing on it around (1.5, 1.5).
+        let mut vertices = vec![
+            [0.01, 0.01, 0.1],
+            [1.99, 0.01, 0.1],
+            [1.99, 1.99, 0.1],
+            [0.01, 1.99, 0.1],
+        ];
+        let mut indices = vec![0, 1, 2, 0, 2, 3];
+        let (peak, peak_indices) = pyramid();
+        vertices.extend(
+            peak.iter()
+                .map(|v| [1.5 + v[0] * 0.2, 1.5 + v[1] * 0.2, 0.1 + v[2] * 0.2]),
+        );
+        indices.extend(peak_indices.iter().map(|i| i + 4));
+
+        let adaptive = Voxels::voxelize_adaptive(&vertices, &indices, 0.25, 0.05);
+        assert!(!adaptive.refined.is_empty());
+        assert!(adaptive
+            .refined
+            .iter()
+            .all(|cell| (5..=6).contains(&cell[0]) && (5..=6).contains(&cell[1])));
+        assert!(adaptive.coarse.grid_positions.contains(&[1, 1, 0]));
+        assert!(!adaptive.fine.grid_positions.is_empty());
+        assert!(adaptive.fine.grid_positions.iter().all(|cell| {
+            let parent = [cell[0] / 5, cell[1] / 5, cell[2] / 5];
+            adaptive.refined.contains(&parent)
+        }));
+    }
+
+    #[test]
+    #[should_panic(expected = "non-finite")]
+    fn voxelize_adaptive_rejects_a_non_finite_vertex() {
+        let (mut vertices, indices) = pyramid();
+        vertices[0][2] = f64::INFINITY;
+        Voxels::voxelize_adaptive(&vertices, &indices, 0.5, 0.1);
+    }
+}
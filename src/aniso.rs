@@ -0,0 +1,106 @@
+use super::voxelize::{CellAnchor, Voxels};
+use num_traits::Float;
+
+/// Voxels whose cells have a separate size along each axis, as produced by `Voxels::voxelize_aniso`.
+pub struct AnisoVoxels<T: Float> {
+    /// The cells in grid units, so `voxels.step` is one.
+    /// Cell `[i, j, k]` spans `[i, i + 1) * step[0]`, `[j, j + 1) * step[1]` and `[k, k + 1) * step[2]`.
+    pub voxels: Voxels<T>,
+    /// The cell size along x, y and z.
+    pub step: [T; 3],
+}
+
+impl<T: Float> AnisoVoxels<T> {
+    /// Gets the anchor point of every voxel in world space.
+    pub fn point_cloud_anchored(&self, anchor: CellAnchor) -> Vec<[T; 3]> {
+        self.voxels
+            .point_cloud_anchored(anchor)
+            .into_iter()
+            .map(|p| self.scale(p))
+            .collect()
+    }
+    /// Gets the exposed faces like `Voxels::vertices_indices`, as boxes stretched to the cell size of each axis.
+    pub fn vertices_indices(&self) -> (Vec<[T; 3]>, Vec<usize>) {
+        let (vertices, indices) = self.voxels.vertices_indices();
+        (
+            vertices.into_iter().map(|p| self.scale(p)).collect(),
+            indices,
+        )
+    }
+    #[inline]
+    fn scale(&self, p: [T; 3]) -> [T; 3] {
+        [
+            p[0] * self.step[0],
+            p[1] * self.step[1],
+            p[2] * self.step[2],
+        ]
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes the surface of a mesh into cells of size `step[0]` by `step[1]` by `step[2]`,
+    /// such as coarse layers in z with fine cells in x and y.
+    ///
+    /// Scaling each axis maps the cells onto a unit grid and keeps which cells a triangle touches,
+    /// so this voxelizes the mesh scaled by the inverse steps with `voxelize` at a step of one.
+    /// Panics if any step is not a positive finite number.
+    pub fn voxelize_aniso(vertices: &[[T; 3]], indices: &[usize], step: [T; 3]) -> AnisoVoxels<T>
+    where
+        T: Send + Sync,
+    {
+        if step.iter().any(|s| !s.is_finite() || *s <= T::epsilon()) {
+            panic!("step should be positive value");
+        }
+        let scaled = vertices
+            .iter()
+            .map(|v| [v[0] / step[0], v[1] / step[1], v[2] / step[2]])
+            .collect::<Vec<_>>();
+        AnisoVoxels {
+            voxels: Voxels::voxelize(&scaled, indices, T::one()),
+            step,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::torus;
+
+    #[test]
+    fn uniform_steps_match_voxelize() {
+        let (vertices, indices) = torus(1.0, 0.4, 16, 8);
+        // A power of two keeps the scaled coordinates exact.
+        let aniso = Voxels::voxelize_aniso(&vertices, &indices, [0.125; 3]);
+        let uniform = Voxels::voxelize(&vertices, &indices, 0.125);
+        assert_eq!(aniso.voxels.grid_positions, uniform.grid_positions);
+        let (v, i) = aniso.vertices_indices();
+        let (expected_v, expected_i) = uniform.vertices_indices();
+        assert_eq!(i, expected_i);
+        assert_eq!(v.len(), expected_v.len());
+    }
+
+    #[test]
+    fn coarse_layers_shrink_one_axis_only() {
+        let (vertices, indices) = torus(1.0, 0.4, 16, 8);
+        let aniso = Voxels::voxelize_aniso(&vertices, &indices, [0.125, 0.125, 0.5]);
+        let (min, max) = aniso.voxels.min_max();
+        let (uniform_min, uniform_max) = Voxels::voxelize(&vertices, &indices, 0.125).min_max();
+        assert_eq!((min[0], max[0]), (uniform_min[0], uniform_max[0]));
+        assert_eq!((min[2], max[2]), (-1, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "step should be positive value")]
+    fn voxelize_aniso_rejects_a_nan_step() {
+        let (vertices, indices) = torus(1.0, 0.4, 16, 8);
+        Voxels::voxelize_aniso(&vertices, &indices, [0.1, f64::NAN, 0.1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "step should be positive value")]
+    fn voxelize_aniso_rejects_an_infinite_step() {
+        let (vertices, indices) = torus(1.0, 0.4, 16, 8);
+        Voxels::voxelize_aniso(&vertices, &indices, [0.1, 0.1, f64::INFINITY]);
+    }
+}
@@ -0,0 +1,125 @@
+use super::vector::Vector3;
+use super::voxelize::{assert_input, build_triangles, default_eps, Voxels, AABB};
+use num_traits::Float;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
+
+/// Voxelizes a mesh one chunk at a time, passing each chunk to `sink` as soon as it is complete.
+///
+/// Chunk `[i, j, k]` holds the cells from `[i, j, k] * chunk_dims` up to the next chunk, and the voxels handed to
+/// `sink` keep their global grid positions, so the union of all chunks equals `Voxels::voxelize`.
+/// Triangles are binned by the chunks their bounding box overlaps first, and each triangle is scanned only over
+/// the cells of the chunk at hand, so only one chunk of voxels is held at a time.
+/// Chunks are visited in ascending order and chunks without voxels are skipped.
+///
+///
+/// # Panics
+///
+/// Panics on the input `Voxels::try_voxelize` rejects, except that an empty mesh gives no chunks,
+/// and if a size in `chunk_dims` is zero or exceeds `i32::MAX`.
+pub fn voxelize_to_chunks<T: Float, F: FnMut([i32; 3], Voxels<T>)>(
+    vertices: &[[T; 3]],
+    indices: &[usize],
+    step: T,
+    chunk_dims: [u32; 3],
+    mut sink: F,
+) {
+    assert_input(vertices, indices, step);
+    let mut dims = [0; 3];
+    for axis in 0..3 {
+        dims[axis] = match i32::try_from(chunk_dims[axis]) {
+            Ok(size) if size > 0 => size,
+            _ => panic!("chunk_dims should be positive values within the range of i32"),
+        };
+    }
+    let chunk_of = |pos: [i32; 3]| {
+        [
+            pos[0].div_euclid(dims[0]),
+            pos[1].div_euclid(dims[1]),
+            pos[2].div_euclid(dims[2]),
+        ]
+    };
+
+    let tris = build_triangles(vertices, indices);
+    let mut bins: BTreeMap<[i32; 3], Vec<usize>> = BTreeMap::new();
+    for (i, tri) in tris.iter().enumerate() {
+        let range = tri.grid_aabb(step);
+        let min = chunk_of([range.min.x, range.min.y, range.min.z]);
+        let max = chunk_of([range.max.x, range.max.y, range.max.z]);
+        for x in min[0]..=max[0] {
+            for y in min[1]..=max[1] {
+                for z in min[2]..=max[2] {
+                    bins.entry([x, y, z]).or_default().push(i);
+                }
+            }
+        }
+    }
+
+    let eps = default_eps();
+    for (chunk, tri_indices) in bins {
+        // The first or last cells of a chunk may lie beyond the range of `i32`, where no triangle reaches.
+        let clamp = |cell: i64| cell.max(i64::from(i32::MIN)).min(i64::from(i32::MAX)) as i32;
+        let first = |axis: usize| i64::from(chunk[axis]) * i64::from(dims[axis]);
+        let bounds = AABB {
+            min: Vector3::new(clamp(first(0)), clamp(first(1)), clamp(first(2))),
+            max: Vector3::new(
+                clamp(first(0) + i64::from(dims[0]) - 1),
+                clamp(first(1) + i64::from(dims[1]) - 1),
+                clamp(first(2) + i64::from(dims[2]) - 1),
+            ),
+        };
+        let mut grid_positions = HashSet::new();
+        for i in tri_indices {
+            grid_positions.extend(tris[i].voxelize_within(step, eps, &bounds));
+        }
+        if !grid_positions.is_empty() {
+            sink(
+                chunk,
+                Voxels {
+                    grid_positions,
+                    step,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::torus;
+
+    #[test]
+    fn chunks_cover_the_voxelization_once() {
+        let (vertices, indices) = torus(1.0, 0.4, 32, 16);
+        let whole = Voxels::voxelize(&vertices, &indices, 0.05);
+        let mut cells = 0;
+        let mut union = HashSet::new();
+        let mut chunks = Vec::new();
+        voxelize_to_chunks(&vertices, &indices, 0.05, [8, 5, 16], |chunk, voxels| {
+            assert!(voxels.grid_positions.iter().all(|pos| {
+                (0..3).all(|axis| pos[axis].div_euclid([8, 5, 16][axis]) == chunk[axis])
+            }));
+            cells += voxels.grid_positions.len();
+            union.extend(voxels.grid_positions);
+            chunks.push(chunk);
+        });
+        assert_eq!(union, whole.grid_positions);
+        assert_eq!(cells, union.len());
+        assert!(chunks.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    #[should_panic(expected = "not a multiple of three")]
+    fn voxelize_to_chunks_rejects_an_incomplete_triangle() {
+        let (vertices, _) = torus(1.0, 0.4, 8, 4);
+        voxelize_to_chunks(&vertices, &[0, 1, 2, 3, 4], 0.05, [8, 8, 8], |_, _| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk_dims")]
+    fn chunk_dims_beyond_i32_are_rejected() {
+        let (vertices, indices) = torus(1.0, 0.4, 8, 4);
+        voxelize_to_chunks(&vertices, &indices, 0.05, [8, u32::MAX, 8], |_, _| {});
+    }
+}
@@ -0,0 +1,136 @@
+use super::vector::Vector3;
+use super::voxelize::{assert_input, build_triangles, default_eps, CellAnchor, Triangle, Voxels};
+use num_traits::Float;
+use std::collections::HashSet;
+
+/// How `Voxels::voxelize_clipped` removes the part of the mesh behind the plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ClipMode {
+    /// Voxelizes the whole mesh, then drops the cells whose center is behind the plane.
+    #[default]
+    CellCenters,
+    /// Clips the triangles to the half-space before voxelizing, so no cell is produced by geometry behind the plane.
+    /// Cells cut by the plane are kept when the remaining part of a triangle touches them.
+    Triangles,
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes only the part of a mesh in front of the plane `plane_normal · p + plane_d >= 0`.
+    ///
+    /// Useful for cross-section views and cutaways. The surface is not capped along the cut.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the input `try_voxelize` rejects, except that an empty mesh gives no voxels.
+    pub fn voxelize_clipped(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        step: T,
+        plane_normal: [T; 3],
+        plane_d: T,
+        mode: ClipMode,
+    ) -> Self
+    where
+        T: Send + Sync,
+    {
+        let normal = Vector3::new(plane_normal[0], plane_normal[1], plane_normal[2]);
+        let distance = |p: &Vector3<T>| normal.dot(p) + plane_d;
+        match mode {
+            ClipMode::CellCenters => {
+                let mut voxels = Voxels::voxelize(vertices, indices, step);
+                let kept = voxels
+                    .grid_positions
+                    .iter()
+                    .filter(|pos| {
+                        let c = voxels.grid_to_world(**pos, CellAnchor::Center);
+                        distance(&Vector3::new(c[0], c[1], c[2])) >= T::zero()
+                    })
+                    .cloned()
+                    .collect();
+                voxels.grid_positions = kept;
+                voxels
+            }
+            ClipMode::Triangles => {
+                assert_input(vertices, indices, step);
+                let eps = default_eps();
+                let mut grid_positions = HashSet::new();
+                for tri in build_triangles(vertices, indices) {
+                    let polygon = clip_to_half_space(&tri.points, distance);
+                    // Fan triangulation is enough since the clipped triangle stays convex.
+                    for i in 1..polygon.len().saturating_sub(1) {
+                        let part = Triangle::new(&polygon[0], &polygon[i], &polygon[i + 1]);
+                        grid_positions.extend(part.voxelize(step, eps));
+                    }
+                }
+                Voxels {
+                    grid_positions,
+                    step,
+                }
+            }
+        }
+    }
+}
+
+/// Clips a triangle to the points where `distance` is non-negative, returning up to four vertices.
+fn clip_to_half_space<T: Float, F: Fn(&Vector3<T>) -> T>(
+    points: &[Vector3<T>; 3],
+    distance: F,
+) -> Vec<Vector3<T>> {
+    let mut clipped = Vec::with_capacity(4);
+    for i in 0..3 {
+        let cur = points[i];
+        let next = points[(i + 1) % 3];
+        let (d_cur, d_next) = (distance(&cur), distance(&next));
+        if d_cur >= T::zero() {
+            clipped.push(cur);
+        }
+        if (d_cur >= T::zero()) != (d_next >= T::zero()) {
+            let t = d_cur / (d_cur - d_next);
+            clipped.push(cur + (next - cur) * t);
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sphere;
+
+    #[test]
+    fn clipping_a_sphere_keeps_the_upper_hemisphere() {
+        let (vertices, indices) = sphere(1.0, 32, 16);
+        let full = Voxels::voxelize(&vertices, &indices, 0.1);
+        let clip =
+            |mode| Voxels::voxelize_clipped(&vertices, &indices, 0.1, [0.0, 0.0, 1.0], 0.0, mode);
+
+        // Cell z spans [z, z + 1) * step, so its center is in front of the plane from z = 0 up.
+        let upper = full
+            .grid_positions
+            .iter()
+            .filter(|pos| pos[2] >= 0)
+            .cloned()
+            .collect::<HashSet<_>>();
+        assert_eq!(clip(ClipMode::CellCenters).grid_positions, upper);
+
+        // Cells just below the plane may touch the clipped triangles along the cut, and no others are kept.
+        let clipped = clip(ClipMode::Triangles).grid_positions;
+        assert!(clipped.is_superset(&upper));
+        assert!(clipped.is_subset(&full.grid_positions));
+        assert!(clipped.iter().all(|pos| pos[2] >= -1));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn clipping_triangles_rejects_an_index_out_of_bounds() {
+        let vertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        Voxels::voxelize_clipped(
+            &vertices,
+            &[0, 1, 3],
+            0.1,
+            [0.0, 0.0, 1.0],
+            0.0,
+            ClipMode::Triangles,
+        );
+    }
+}
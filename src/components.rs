@@ -0,0 +1,614 @@
+use super::dense::{check_cells, span_dims, DenseGrid};
+use super::palette::distinct_colors;
+use super::voxelize::Voxels;
+use num_traits::Float;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Neighborhood used when deciding whether two voxels are adjacent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    /// Voxels sharing a face.
+    Six,
+    /// Voxels sharing a face, an edge or a corner.
+    TwentySix,
+}
+
+impl Connectivity {
+    pub(crate) fn offsets(self) -> Vec<[i32; 3]> {
+        match self {
+            Connectivity::Six => vec![
+                [1, 0, 0],
+                [-1, 0, 0],
+                [0, 1, 0],
+                [0, -1, 0],
+                [0, 0, 1],
+                [0, 0, -1],
+            ],
+            Connectivity::TwentySix => {
+                let mut offsets = Vec::with_capacity(26);
+                for x in -1..2 {
+                    for y in -1..2 {
+                        for z in -1..2 {
+                            if x != 0 || y != 0 || z != 0 {
+                                offsets.push([x, y, z]);
+                            }
+                        }
+                    }
+                }
+                offsets
+            }
+        }
+    }
+}
+
+/// Offsets a grid position, or returns `None` if it would leave the range of `i32`.
+#[inline]
+pub(crate) fn offset_pos(pos: [i32; 3], offset: [i32; 3]) -> Option<[i32; 3]> {
+    Some([
+        pos[0].checked_add(offset[0])?,
+        pos[1].checked_add(offset[1])?,
+        pos[2].checked_add(offset[2])?,
+    ])
+}
+
+/// Splits `positions` into connected components with an iterative flood fill.
+/// Components are ordered by their smallest grid position.
+pub(crate) fn components(
+    positions: &HashSet<[i32; 3]>,
+    connectivity: Connectivity,
+) -> Vec<Vec<[i32; 3]>> {
+    let offsets = connectivity.offsets();
+    let mut seeds = positions.iter().cloned().collect::<Vec<_>>();
+    seeds.sort_unstable();
+    let mut visited = HashSet::with_capacity(positions.len());
+    let mut components = Vec::new();
+    let mut queue = VecDeque::new();
+    for seed in seeds {
+        if !visited.insert(seed) {
+            continue;
+        }
+        let mut component = vec![seed];
+        queue.push_back(seed);
+        while let Some(pos) = queue.pop_front() {
+            for next in offsets.iter().filter_map(|offset| offset_pos(pos, *offset)) {
+                if positions.contains(&next) && visited.insert(next) {
+                    component.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+impl<T: Float> Voxels<T> {
+    /// Labels connected components on a dense grid spanning `min_max`.
+    ///
+    /// Each occupied cell holds its 1-based component id and empty cells hold 0.
+    /// Also returns the number of components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of cells within `min_max` overflows `usize`.
+    pub fn label_components(&self, connectivity: Connectivity) -> (DenseGrid<u32>, usize) {
+        if self.grid_positions.is_empty() {
+            return (DenseGrid::filled([0, 0, 0], [0, 0, 0], 0), 0);
+        }
+        let (min, max) = self.min_max();
+        let dims = span_dims(min, max);
+        if let Err(e) = check_cells(dims, usize::MAX) {
+            panic!("{}", e);
+        }
+        let mut labels = DenseGrid::filled(dims, min, 0u32);
+        let components = components(&self.grid_positions, connectivity);
+        for (i, component) in components.iter().enumerate() {
+            for pos in component {
+                if let Some(label) = labels.get_mut(*pos) {
+                    *label = i as u32 + 1;
+                }
+            }
+        }
+        (labels, components.len())
+    }
+    /// Iterates over the occupied neighbors of `pos`, which turns the voxels into a graph for path searches.
+    /// `pos` itself need not be occupied, and neighbors beyond the range of `i32` are skipped.
+    pub fn neighbors(
+        &self,
+        pos: [i32; 3],
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = [i32; 3]> + '_ {
+        connectivity
+            .offsets()
+            .into_iter()
+            .filter_map(move |offset| offset_pos(pos, offset))
+            .filter(move |next| self.grid_positions.contains(next))
+    }
+    /// Finds the empty regions enclosed by the voxels, such as air trapped inside a print.
+    ///
+    /// Empty cells within `min_max` are split into components under `connectivity`, and those reaching
+    /// the boundary of `min_max` (hence the outside) are dropped. Cavities are ordered by their smallest grid position.
+    ///
+    /// The outside is marked by a flood fill on a bit grid over the padded bounds, so only the cells of the
+    /// cavities themselves are collected into sets.
+    pub fn enclosed_cavities(&self, connectivity: Connectivity) -> Vec<HashSet<[i32; 3]>> {
+        let mut visited = match self.exterior_connected(connectivity) {
+            Some(exterior) => exterior,
+            None => return Vec::new(),
+        };
+        let (min, max) = self.min_max();
+        let offsets = connectivity.offsets();
+        let mut cavities = Vec::new();
+        // Seeds are met in ascending order, and each is the smallest position of its cavity.
+        for x in min[0]..=max[0] {
+            for y in min[1]..=max[1] {
+                for z in min[2]..=max[2] {
+                    let seed = [x, y, z];
+                    if visited.get(seed) || self.grid_positions.contains(&seed) {
+                        continue;
+                    }
+                    let mut cavity = HashSet::new();
+                    let mut queue = VecDeque::new();
+                    visited.set(seed);
+                    queue.push_back(seed);
+                    while let Some(pos) = queue.pop_front() {
+                        cavity.insert(pos);
+                        for next in offsets.iter().filter_map(|offset| offset_pos(pos, *offset)) {
+                            if !visited.get(next) && !self.grid_positions.contains(&next) {
+                                visited.set(next);
+                                queue.push_back(next);
+                            }
+                        }
+                    }
+                    cavities.push(cavity);
+                }
+            }
+        }
+        cavities
+    }
+    /// Fills each enclosed chamber, such as the cells of a voxelized honeycomb, and returns the filled chambers.
+    ///
+    /// Only the existing voxels are treated as walls, so dividers stay in place and every chamber is returned
+    /// separately, ordered by its smallest grid position. Unlike `fill`, no parity scans are applied,
+    /// so a surface with gaps fills nothing.
+    pub fn fill_chambers(&mut self) -> Vec<HashSet<[i32; 3]>> {
+        let chambers = self.enclosed_cavities(Connectivity::Six);
+        for chamber in chambers.iter() {
+            self.grid_positions.extend(chamber.iter().cloned());
+        }
+        chambers
+    }
+    /// Checks whether all voxels form a single connected component.
+    ///
+    /// Two flood fills run in turns, from the smallest and the largest grid positions. The answer is `false` as soon as
+    /// either runs out of cells before meeting the other, so a small stray component is found after visiting
+    /// about twice its size. Once they meet, the answer is `true` as soon as every voxel has been visited.
+    /// This is cheaper than labeling every component. An empty set counts as connected.
+    pub fn is_connected(&self, connectivity: Connectivity) -> bool {
+        let len = self.grid_positions.len();
+        let mut positions = self.grid_positions.iter();
+        let first = match positions.next() {
+            Some(first) => *first,
+            None => return true,
+        };
+        let (min, max) = positions.fold((first, first), |(min, max), pos| {
+            (min.min(*pos), max.max(*pos))
+        });
+        if min == max {
+            return true;
+        }
+        let offsets = connectivity.offsets();
+        // Which flood fill reached each visited cell.
+        let mut owner = HashMap::with_capacity(len);
+        owner.insert(min, 0);
+        owner.insert(max, 1);
+        let mut queues = [VecDeque::new(), VecDeque::new()];
+        queues[0].push_back(min);
+        queues[1].push_back(max);
+        let mut met = false;
+        let mut turn = 0;
+        while !met || owner.len() < len {
+            let pos = match queues[turn].pop_front() {
+                Some(pos) => pos,
+                None => return false,
+            };
+            for next in offsets.iter().filter_map(|offset| offset_pos(pos, *offset)) {
+                if !self.grid_positions.contains(&next) {
+                    continue;
+                }
+                match owner.get(&next) {
+                    None => {
+                        owner.insert(next, turn);
+                        queues[turn].push_back(next);
+                    }
+                    Some(other) if *other != turn && !met => {
+                        // Both belong to one component, so a single flood fill goes on with both queues.
+                        met = true;
+                        let rest = std::mem::take(&mut queues[1 - turn]);
+                        queues[turn].extend(rest);
+                    }
+                    Some(_) => {}
+                }
+            }
+            if !met {
+                turn = 1 - turn;
+            }
+        }
+        true
+    }
+    /// Splits the voxels into one `Voxels` per connected component, each keeping `step`.
+    /// Components are ordered by their smallest grid position.
+    pub fn into_parts(self, connectivity: Connectivity) -> Vec<Voxels<T>> {
+        let step = self.step;
+        components(&self.grid_positions, connectivity)
+            .into_iter()
+            .map(|component| Voxels {
+                grid_positions: component.into_iter().collect(),
+                step,
+            })
+            .collect()
+    }
+    /// Splits the voxels into one `Voxels` per connected component like `into_parts`, without consuming them.
+    /// Components are ordered largest first, and components of equal size by their smallest grid position.
+    pub fn connected_components(&self, connectivity: Connectivity) -> Vec<Voxels<T>> {
+        let mut components = components(&self.grid_positions, connectivity);
+        components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+        components
+            .into_iter()
+            .map(|component| Voxels {
+                grid_positions: component.into_iter().collect(),
+                step: self.step,
+            })
+            .collect()
+    }
+    /// Removes every connected component with fewer than `min_component_size` voxels, such as specks
+    /// left by voxelizing a noisy scan. Returns the number of voxels removed.
+    pub fn denoise(&mut self, min_component_size: usize, connectivity: Connectivity) -> usize {
+        let mut removed = 0;
+        for component in components(&self.grid_positions, connectivity) {
+            if component.len() < min_component_size {
+                for pos in component.iter() {
+                    self.grid_positions.remove(pos);
+                }
+                removed += component.len();
+            }
+        }
+        removed
+    }
+    /// Assigns every voxel the color of its connected component, taken from `palette::distinct_colors`.
+    /// Components are colored in the order of their smallest grid position, so the result is deterministic.
+    pub fn colorize_by_component(&self, connectivity: Connectivity) -> HashMap<[i32; 3], [u8; 3]> {
+        let components = components(&self.grid_positions, connectivity);
+        let colors = distinct_colors(components.len());
+        components
+            .iter()
+            .zip(colors)
+            .flat_map(|(component, color)| component.iter().map(move |pos| (*pos, color)))
+            .collect()
+    }
+    /// Builds the adjacency graph of the occupied cells, for use with petgraph's algorithms.
+    ///
+    /// Nodes are added in ascending grid position order and hold their positions.
+    #[cfg(feature = "petgraph")]
+    pub fn to_graph(&self, connectivity: Connectivity) -> petgraph::graph::UnGraph<[i32; 3], ()> {
+        let mut positions = self.grid_positions.iter().cloned().collect::<Vec<_>>();
+        positions.sort_unstable();
+        let mut graph = petgraph::graph::UnGraph::with_capacity(positions.len(), 0);
+        let nodes = positions
+            .iter()
+            .map(|pos| (*pos, graph.add_node(*pos)))
+            .collect::<HashMap<_, _>>();
+        for pos in positions.iter() {
+            // Each edge is added once, from its smaller end.
+            for next in self.neighbors(*pos, connectivity).filter(|next| next > pos) {
+                graph.add_edge(nodes[pos], nodes[&next], ());
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The cells of an `n` by `n` by `n` block, without its interior when `hollow`.
+    fn block(n: i32, hollow: bool) -> Voxels<f64> {
+        let mut grid_positions = HashSet::new();
+        for x in 0..n {
+            for y in 0..n {
+                for z in 0..n {
+                    let on_shell = [x, y, z].iter().any(|c| *c == 0 || *c == n - 1);
+                    if on_shell || !hollow {
+                        grid_positions.insert([x, y, z]);
+                    }
+                }
+            }
+        }
+        Voxels {
+            grid_positions,
+            step: 0.1,
+        }
+    }
+
+    /// Two 2 by 2 by 2 blocks three cells apart along x.
+    fn two_blobs() -> Voxels<f64> {
+        let mut grid_positions = HashSet::new();
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    grid_positions.insert([x, y, z]);
+                    grid_positions.insert([x + 5, y, z]);
+                }
+            }
+        }
+        Voxels {
+            grid_positions,
+            step: 0.1,
+        }
+    }
+
+    #[test]
+    fn two_blobs_get_two_labels() {
+        let voxels = two_blobs();
+        let (labels, count) = voxels.label_components(Connectivity::TwentySix);
+        assert_eq!(count, 2);
+        assert_eq!(labels.dims, [7, 2, 2]);
+        assert_eq!(labels.origin, [0, 0, 0]);
+        for x in 0..7 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    let expected = match x {
+                        0 | 1 => 1,
+                        5 | 6 => 2,
+                        _ => 0,
+                    };
+                    assert_eq!(labels.get([x, y, z]), Some(&expected));
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "dense grid would exceed")]
+    fn label_components_rejects_a_grid_spanning_the_whole_range() {
+        let voxels = Voxels {
+            grid_positions: [[i32::MIN; 3], [i32::MAX; 3]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        voxels.label_components(Connectivity::Six);
+    }
+
+    #[test]
+    fn one_blob_is_connected_and_two_are_not() {
+        let mut blob = two_blobs();
+        blob.grid_positions.retain(|pos| pos[0] < 2);
+        for connectivity in [Connectivity::Six, Connectivity::TwentySix].iter() {
+            assert!(blob.is_connected(*connectivity));
+            assert!(!two_blobs().is_connected(*connectivity));
+        }
+        assert!(block(5, true).is_connected(Connectivity::Six));
+    }
+
+    #[test]
+    fn is_connected_follows_the_connectivity() {
+        let diagonal = Voxels {
+            grid_positions: [[0, 0, 0], [1, 1, 0], [2, 2, 1]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        assert!(!diagonal.is_connected(Connectivity::Six));
+        assert!(diagonal.is_connected(Connectivity::TwentySix));
+        // A stray cell beside a large block, found before the block is flooded.
+        let mut stray = block(6, false);
+        stray.grid_positions.insert([9, 9, 9]);
+        assert!(!stray.is_connected(Connectivity::TwentySix));
+    }
+
+    #[test]
+    fn into_parts_splits_two_blobs_in_order() {
+        let mut voxels = two_blobs();
+        voxels.grid_positions.insert([6, 2, 0]);
+        let parts = voxels.into_parts(Connectivity::Six);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].grid_positions.len(), 8);
+        assert_eq!(parts[1].grid_positions.len(), 9);
+        assert!(parts[0].grid_positions.iter().all(|pos| pos[0] < 2));
+        assert!(parts[1].grid_positions.contains(&[6, 2, 0]));
+        assert!(parts.iter().all(|part| part.step == 0.1));
+    }
+    #[test]
+    fn two_components_get_two_colors() {
+        let colors = two_blobs().colorize_by_component(Connectivity::Six);
+        assert_eq!(colors.len(), 16);
+        let palette = distinct_colors(2);
+        assert!(colors
+            .iter()
+            .all(|(pos, color)| *color == palette[if pos[0] < 2 { 0 } else { 1 }]));
+        assert_ne!(palette[0], palette[1]);
+    }
+
+    #[test]
+    fn interior_cells_of_a_line_have_two_neighbors() {
+        let line = Voxels {
+            grid_positions: (0..6).map(|y| [2, y, -1]).collect(),
+            step: 0.1,
+        };
+        for y in 1..5 {
+            let mut neighbors = line
+                .neighbors([2, y, -1], Connectivity::Six)
+                .collect::<Vec<_>>();
+            neighbors.sort_unstable();
+            assert_eq!(neighbors, vec![[2, y - 1, -1], [2, y + 1, -1]]);
+        }
+        assert_eq!(line.neighbors([2, 0, -1], Connectivity::Six).count(), 1);
+        // The position itself need not be occupied.
+        assert_eq!(line.neighbors([3, 3, -1], Connectivity::Six).count(), 1);
+        assert_eq!(
+            line.neighbors([3, 3, -1], Connectivity::TwentySix).count(),
+            3
+        );
+        let edge = Voxels {
+            grid_positions: [[i32::MAX, 0, 0]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        assert_eq!(
+            edge.neighbors([i32::MAX, 0, 0], Connectivity::TwentySix)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn denoise_removes_specks_and_keeps_the_body() {
+        let mut noisy = block(4, false);
+        noisy.grid_positions.insert([10, 10, 10]);
+        noisy.grid_positions.insert([10, 11, 10]);
+        // Touches the corner of the block only.
+        noisy.grid_positions.insert([4, 4, 4]);
+
+        let mut voxels = Voxels::new(&noisy.grid_positions, noisy.step);
+        assert_eq!(voxels.denoise(3, Connectivity::TwentySix), 2);
+        let mut body = block(4, false);
+        body.grid_positions.insert([4, 4, 4]);
+        assert_eq!(voxels.grid_positions, body.grid_positions);
+
+        let mut voxels = Voxels::new(&noisy.grid_positions, noisy.step);
+        assert_eq!(voxels.denoise(3, Connectivity::Six), 3);
+        assert_eq!(voxels.grid_positions, block(4, false).grid_positions);
+
+        let mut voxels = Voxels::new(&noisy.grid_positions, noisy.step);
+        assert_eq!(voxels.denoise(1, Connectivity::Six), 0);
+        assert_eq!(voxels.grid_positions, noisy.grid_positions);
+    }
+
+    #[test]
+    fn fill_chambers_fills_each_cell_of_a_honeycomb_separately() {
+        // A 7 by 4 by 4 box split by a wall at x = 3 into two 2 by 2 by 2 chambers.
+        let mut grid_positions = HashSet::new();
+        for x in 0..7 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    let wall =
+                        x == 0 || x == 3 || x == 6 || [y, z].iter().any(|c| *c == 0 || *c == 3);
+                    if wall {
+                        grid_positions.insert([x, y, z]);
+                    }
+                }
+            }
+        }
+        let walls = grid_positions.len();
+        let mut voxels = Voxels {
+            grid_positions,
+            step: 0.1,
+        };
+        let chamber = |x0: i32| {
+            let mut cells = HashSet::new();
+            for x in x0..(x0 + 2) {
+                for y in 1..3 {
+                    for z in 1..3 {
+                        cells.insert([x, y, z]);
+                    }
+                }
+            }
+            cells
+        };
+        let mut open = Voxels::new(&voxels.grid_positions, 0.1);
+        assert_eq!(voxels.fill_chambers(), vec![chamber(1), chamber(4)]);
+        assert_eq!(voxels.grid_positions.len(), walls + 16);
+        assert_eq!(voxels.grid_positions.len(), 7 * 4 * 4);
+
+        // A hole in the wall of the second chamber opens it to the outside.
+        open.grid_positions.remove(&[6, 1, 1]);
+        assert_eq!(open.fill_chambers(), vec![chamber(1)]);
+        assert_eq!(open.grid_positions.len(), walls - 1 + 8);
+    }
+
+    #[test]
+    fn connected_components_are_largest_first_then_by_smallest_position() {
+        let mut voxels = block(2, false);
+        let mut add = |cells: &[[i32; 3]]| voxels.grid_positions.extend(cells.iter().cloned());
+        add(&[[20, 0, 0]]);
+        add(&[[10, 0, 0], [11, 0, 0], [12, 0, 0]]);
+        add(&[[-5, 0, 0]]);
+        add(&[[-3, 9, 0], [-2, 9, 0], [-1, 9, 0]]);
+        let parts = voxels.connected_components(Connectivity::Six);
+        let summary = parts
+            .iter()
+            .map(|part| (part.grid_positions.len(), part.min_max().0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (8, [0, 0, 0]),
+                (3, [-3, 9, 0]),
+                (3, [10, 0, 0]),
+                (1, [-5, 0, 0]),
+                (1, [20, 0, 0]),
+            ]
+        );
+        assert!(parts.iter().all(|part| part.step == 0.1));
+        assert_eq!(
+            parts
+                .iter()
+                .map(|part| part.grid_positions.len())
+                .sum::<usize>(),
+            16
+        );
+    }
+
+    #[test]
+    fn hollow_box_has_one_cavity() {
+        let cavities = block(5, true).enclosed_cavities(Connectivity::Six);
+        assert_eq!(cavities.len(), 1);
+        assert_eq!(cavities[0].len(), 27);
+        assert!(cavities[0]
+            .iter()
+            .all(|pos| pos.iter().all(|c| (1..4).contains(c))));
+    }
+
+    #[test]
+    fn solid_block_has_no_cavity() {
+        for connectivity in [Connectivity::Six, Connectivity::TwentySix].iter() {
+            assert!(block(5, false).enclosed_cavities(*connectivity).is_empty());
+        }
+    }
+
+    #[test]
+    fn cavities_follow_the_connectivity() {
+        // Opening an edge cell joins the center to the outside diagonally, but not through a face.
+        let mut voxels = block(3, true);
+        voxels.grid_positions.remove(&[0, 0, 1]);
+        let six = voxels.enclosed_cavities(Connectivity::Six);
+        assert_eq!(
+            six,
+            vec![[[1, 1, 1]].iter().cloned().collect::<HashSet<_>>()]
+        );
+        assert!(voxels.enclosed_cavities(Connectivity::TwentySix).is_empty());
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn graph_has_a_node_per_cell_and_an_edge_per_neighbor_pair() {
+        let line = Voxels {
+            grid_positions: (0..5).map(|x| [x, 0, 0]).collect(),
+            step: 0.1,
+        };
+        let graph = line.to_graph(Connectivity::Six);
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(graph.edge_count(), 4);
+        let positions = graph.node_weights().cloned().collect::<Vec<_>>();
+        assert_eq!(positions, (0..5).map(|x| [x, 0, 0]).collect::<Vec<_>>());
+
+        // A 2 by 2 by 2 block has 12 faces shared, and every pair of its cells touches under 26-connectivity.
+        assert_eq!(block(2, false).to_graph(Connectivity::Six).edge_count(), 12);
+        assert_eq!(
+            block(2, false)
+                .to_graph(Connectivity::TwentySix)
+                .edge_count(),
+            28
+        );
+        let blobs = two_blobs().to_graph(Connectivity::TwentySix);
+        assert_eq!(blobs.node_count(), 16);
+        assert_eq!(petgraph::algo::connected_components(&blobs), 2);
+    }
+}
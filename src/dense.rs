@@ -0,0 +1,441 @@
+use super::voxelize::{VoxelizeError, Voxels};
+use num_traits::Float;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// Converts a grid position into a `x + nx * (y + ny * z)` index, or `None` if it lies outside the box.
+#[inline]
+pub(crate) fn dense_index(origin: [i32; 3], dims: [usize; 3], pos: [i32; 3]) -> Option<usize> {
+    let mut local = [0usize; 3];
+    for axis in 0..3 {
+        let d = i64::from(pos[axis]) - i64::from(origin[axis]);
+        if d < 0 || d >= dims[axis] as i64 {
+            return None;
+        }
+        local[axis] = d as usize;
+    }
+    Some(local[0] + dims[0] * (local[1] + dims[1] * local[2]))
+}
+
+/// Fails with `VoxelizeError::TooManyCells` unless a box of `dims` cells holds at most `max_cells` cells.
+#[inline]
+pub(crate) fn check_cells(dims: [usize; 3], max_cells: usize) -> Result<(), VoxelizeError> {
+    let cells = dims[0]
+        .checked_mul(dims[1])
+        .and_then(|c| c.checked_mul(dims[2]));
+    match cells {
+        Some(cells) if cells <= max_cells => Ok(()),
+        _ => Err(VoxelizeError::TooManyCells { max_cells }),
+    }
+}
+
+/// Number of cells along each axis of the box from `min` to `max` inclusive, computed in `i64` so that
+/// boxes spanning the whole range of `i32` do not overflow.
+#[inline]
+pub(crate) fn span_dims(min: [i32; 3], max: [i32; 3]) -> [usize; 3] {
+    let mut dims = [0usize; 3];
+    for axis in 0..3 {
+        dims[axis] = (i64::from(max[axis]) - i64::from(min[axis]) + 1) as usize;
+    }
+    dims
+}
+
+/// Gets the box from `min` to `max` padded by one cell on every side where the range of `i32` allows,
+/// as its first cell and its number of cells along each axis.
+#[inline]
+pub(crate) fn padded_box(min: [i32; 3], max: [i32; 3]) -> ([i32; 3], [usize; 3]) {
+    let mut origin = min;
+    let mut end = max;
+    for axis in 0..3 {
+        origin[axis] = min[axis].saturating_sub(1);
+        end[axis] = max[axis].saturating_add(1);
+    }
+    (origin, span_dims(origin, end))
+}
+
+/// Lists the cells on the six faces of the box of `dims` cells starting at `origin`.
+pub(crate) fn border_cells(origin: [i32; 3], dims: [usize; 3]) -> Vec<[i32; 3]> {
+    let mut cells = Vec::new();
+    let at = |axis: usize, i: usize| (i64::from(origin[axis]) + i as i64) as i32;
+    for x in 0..dims[0] {
+        for y in 0..dims[1] {
+            let on_side = x == 0 || x + 1 == dims[0] || y == 0 || y + 1 == dims[1];
+            let step = if on_side {
+                1
+            } else {
+                dims[2].saturating_sub(1).max(1)
+            };
+            for z in (0..dims[2]).step_by(step) {
+                cells.push([at(0, x), at(1, y), at(2, z)]);
+            }
+        }
+    }
+    cells
+}
+
+/// A dense 3D array covering an axis-aligned box of grid positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseGrid<V> {
+    /// Cell values, laid out as `x + nx * (y + ny * z)` relative to `origin`.
+    pub data: Vec<V>,
+    /// Number of cells along each axis (`[nx, ny, nz]`).
+    pub dims: [usize; 3],
+    /// Grid position of the cell stored at index 0.
+    pub origin: [i32; 3],
+}
+
+impl<V: Clone> DenseGrid<V> {
+    pub(crate) fn filled(dims: [usize; 3], origin: [i32; 3], value: V) -> Self {
+        Self {
+            data: vec![value; dims[0] * dims[1] * dims[2]],
+            dims,
+            origin,
+        }
+    }
+}
+
+impl<V> DenseGrid<V> {
+    /// Converts a grid position into an index of `data`, or `None` if it lies outside the grid.
+    #[inline]
+    pub fn index(&self, pos: [i32; 3]) -> Option<usize> {
+        dense_index(self.origin, self.dims, pos)
+    }
+    /// Gets the value at a grid position.
+    #[inline]
+    pub fn get(&self, pos: [i32; 3]) -> Option<&V> {
+        self.index(pos).map(|i| &self.data[i])
+    }
+    /// Gets a mutable reference to the value at a grid position.
+    #[inline]
+    pub fn get_mut(&mut self, pos: [i32; 3]) -> Option<&mut V> {
+        match self.index(pos) {
+            Some(i) => Some(&mut self.data[i]),
+            None => None,
+        }
+    }
+}
+
+/// A dense occupancy grid packing one bit per cell into `u64` words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitGrid {
+    /// Occupancy bits, cell `i` (laid out as `x + nx * (y + ny * z)`) is bit `i % 64` of word `i / 64`.
+    pub words: Vec<u64>,
+    /// Number of cells along each axis (`[nx, ny, nz]`).
+    pub dims: [usize; 3],
+    /// Grid position of the cell stored at index 0.
+    pub origin: [i32; 3],
+}
+
+impl BitGrid {
+    /// Creates an empty grid, reusing `buffer` for the words.
+    pub(crate) fn with_buffer(dims: [usize; 3], origin: [i32; 3], mut buffer: Vec<u64>) -> Self {
+        let cells = dims[0] * dims[1] * dims[2];
+        buffer.clear();
+        buffer.resize((cells + 63) / 64, 0);
+        Self {
+            words: buffer,
+            dims,
+            origin,
+        }
+    }
+    /// Gets the number of cells.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.dims[0] * self.dims[1] * self.dims[2]
+    }
+    /// Checks whether the grid has no cells.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Converts a grid position into a cell index, or `None` if it lies outside the grid.
+    #[inline]
+    pub fn index(&self, pos: [i32; 3]) -> Option<usize> {
+        dense_index(self.origin, self.dims, pos)
+    }
+    /// Checks whether a grid position is occupied.
+    #[inline]
+    pub fn get(&self, pos: [i32; 3]) -> bool {
+        match self.index(pos) {
+            Some(i) => self.words[i / 64] & (1 << (i % 64)) != 0,
+            None => false,
+        }
+    }
+    /// Marks a grid position as occupied. Positions outside the grid are ignored.
+    #[inline]
+    pub fn set(&mut self, pos: [i32; 3]) {
+        if let Some(i) = self.index(pos) {
+            self.words[i / 64] |= 1 << (i % 64);
+        }
+    }
+    /// Counts the occupied cells. Runs in parallel with the `rayon` feature.
+    pub fn count_ones(&self) -> usize {
+        #[cfg(feature = "rayon")]
+        {
+            self.words.par_chunks(WORDS_PER_TASK).map(count_ones).sum()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            count_ones(&self.words)
+        }
+    }
+    /// Marks the cells occupied in either grid.
+    ///
+    /// Panics if the grids do not have the same `dims` and `origin`.
+    pub fn union_with(&mut self, other: &BitGrid) {
+        self.combine(other, |a, b| a | b);
+    }
+    /// Keeps only the cells occupied in both grids.
+    ///
+    /// Panics if the grids do not have the same `dims` and `origin`.
+    pub fn intersect_with(&mut self, other: &BitGrid) {
+        self.combine(other, |a, b| a & b);
+    }
+    /// Clears the cells occupied in `other`.
+    ///
+    /// Panics if the grids do not have the same `dims` and `origin`.
+    pub fn difference_with(&mut self, other: &BitGrid) {
+        self.combine(other, |a, b| a & !b);
+    }
+    /// Combines the words pairwise, in parallel chunks with the `rayon` feature.
+    fn combine<F: Fn(u64, u64) -> u64 + Sync>(&mut self, other: &BitGrid, op: F) {
+        assert!(
+            self.dims == other.dims && self.origin == other.origin,
+            "grids should cover the same cells"
+        );
+        #[cfg(feature = "rayon")]
+        {
+            self.words
+                .par_chunks_mut(WORDS_PER_TASK)
+                .zip(other.words.par_chunks(WORDS_PER_TASK))
+                .for_each(|(a, b)| combine_words(a, b, &op));
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            combine_words(&mut self.words, &other.words, &op);
+        }
+    }
+    /// Takes the word buffer back, e.g. to pass it to `Voxels::try_into_dense_with_buffer` again.
+    pub fn into_words(self) -> Vec<u64> {
+        self.words
+    }
+}
+
+/// Number of words handled by one parallel task, large enough to amortize the scheduling.
+#[cfg(feature = "rayon")]
+const WORDS_PER_TASK: usize = 1 << 14;
+
+// Plain loops over whole words, which the compiler vectorizes.
+#[inline]
+fn count_ones(words: &[u64]) -> usize {
+    words.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+#[inline]
+fn combine_words<F: Fn(u64, u64) -> u64>(a: &mut [u64], b: &[u64], op: &F) {
+    for (a, b) in a.iter_mut().zip(b.iter()) {
+        *a = op(*a, *b);
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Converts the voxels into a dense occupancy grid spanning `min_max`.
+    ///
+    /// Returns `VoxelizeError::TooManyCells` instead of allocating when the grid would exceed `max_cells` cells,
+    /// which matters for sparse sets spread over huge bounds.
+    pub fn try_into_dense(&self, max_cells: usize) -> Result<BitGrid, VoxelizeError> {
+        self.try_into_dense_with_buffer(max_cells, Vec::new())
+    }
+    /// Same as `try_into_dense`, but reuses `buffer` for the words to avoid reallocating in loops.
+    pub fn try_into_dense_with_buffer(
+        &self,
+        max_cells: usize,
+        buffer: Vec<u64>,
+    ) -> Result<BitGrid, VoxelizeError> {
+        if self.grid_positions.is_empty() {
+            return Ok(BitGrid::with_buffer([0, 0, 0], [0, 0, 0], buffer));
+        }
+        let (min, max) = self.min_max();
+        let dims = span_dims(min, max);
+        check_cells(dims, max_cells)?;
+        let mut grid = BitGrid::with_buffer(dims, min, buffer);
+        for pos in self.grid_positions.iter() {
+            grid.set(*pos);
+        }
+        Ok(grid)
+    }
+    /// Converts the voxels into a flat occupancy array spanning `min_max`, with one `bool` per cell.
+    ///
+    /// Returns the array, laid out as `x + nx * (y + ny * z)`, its dimensions `[nx, ny, nz]`, and the grid
+    /// position of the cell at index 0. An empty set gives an empty array with zero dimensions at the origin.
+    /// Unlike `try_into_dense`, there is no limit on the number of cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of cells within `min_max` overflows `usize`.
+    pub fn to_dense(&self) -> (Vec<bool>, [usize; 3], [i32; 3]) {
+        if self.grid_positions.is_empty() {
+            return (Vec::new(), [0, 0, 0], [0, 0, 0]);
+        }
+        let (min, max) = self.min_max();
+        let dims = span_dims(min, max);
+        if let Err(e) = check_cells(dims, usize::MAX) {
+            panic!("{}", e);
+        }
+        let mut data = vec![false; dims[0] * dims[1] * dims[2]];
+        for pos in self.grid_positions.iter() {
+            data[dense_index(min, dims, *pos).unwrap()] = true;
+        }
+        (data, dims, min)
+    }
+    /// Builds voxels from a flat occupancy array as returned by `to_dense`.
+    ///
+    /// Panics if `data` does not hold exactly `nx * ny * nz` cells, or if a cell lies outside the range of `i32`.
+    pub fn from_dense(data: &[bool], dims: [usize; 3], origin: [i32; 3], step: T) -> Self {
+        let cells = dims[0]
+            .checked_mul(dims[1])
+            .and_then(|c| c.checked_mul(dims[2]));
+        assert_eq!(
+            cells,
+            Some(data.len()),
+            "data should have nx * ny * nz cells"
+        );
+        let mut grid_positions = HashSet::new();
+        for (i, occupied) in data.iter().enumerate() {
+            if *occupied {
+                let local = [i % dims[0], i / dims[0] % dims[1], i / dims[0] / dims[1]];
+                let mut pos = [0; 3];
+                for axis in 0..3 {
+                    pos[axis] = i32::try_from(i64::from(origin[axis]) + local[axis] as i64)
+                        .expect("cell should be within the range of i32");
+                }
+                grid_positions.insert(pos);
+            }
+        }
+        Voxels {
+            grid_positions,
+            step,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A grid spanning several parallel chunks, with cells marked by `occupied`.
+    fn grid<F: Fn(usize) -> bool>(occupied: F) -> BitGrid {
+        let mut grid = BitGrid::with_buffer([128, 128, 100], [-3, 0, 7], Vec::new());
+        for i in (0..grid.len()).filter(|i| occupied(*i)) {
+            grid.words[i / 64] |= 1 << (i % 64);
+        }
+        grid
+    }
+
+    #[test]
+    fn word_ops_match_cell_by_cell_results() {
+        let a = |i: usize| i % 3 == 0 || i % 7 == 1;
+        let b = |i: usize| i % 5 < 2;
+        let mut union = grid(a);
+        union.union_with(&grid(b));
+        let mut intersection = grid(a);
+        intersection.intersect_with(&grid(b));
+        let mut difference = grid(a);
+        difference.difference_with(&grid(b));
+
+        assert_eq!(union, grid(|i| a(i) || b(i)));
+        assert_eq!(intersection, grid(|i| a(i) && b(i)));
+        assert_eq!(difference, grid(|i| a(i) && !b(i)));
+        assert_eq!(
+            union.count_ones(),
+            (0..union.len()).filter(|i| a(*i) || b(*i)).count()
+        );
+    }
+
+    #[test]
+    fn to_dense_and_from_dense_round_trip() {
+        let cells = [[-1, 0, 2], [1, 3, 2], [0, 0, 4], [1, 0, 2]];
+        let voxels = Voxels {
+            grid_positions: cells.iter().cloned().collect::<HashSet<_>>(),
+            step: 0.25,
+        };
+        let (data, dims, origin) = voxels.to_dense();
+        assert_eq!((dims, origin), ([3, 4, 3], [-1, 0, 2]));
+        assert_eq!(data.len(), 36);
+        assert_eq!(data.iter().filter(|c| **c).count(), 4);
+        // Cell [1, 3, 2] lies at x 2, y 3 and z 0 from the origin.
+        assert!(data[2 + 3 * 3]);
+        let back = Voxels::from_dense(&data, dims, origin, 0.25);
+        assert_eq!(back.grid_positions, voxels.grid_positions);
+        assert_eq!(back.step, 0.25);
+
+        let empty = Voxels::<f64>::with_capacity(0, 0.25);
+        assert_eq!(empty.to_dense(), (Vec::new(), [0, 0, 0], [0, 0, 0]));
+        assert!(Voxels::from_dense(&[], [0, 0, 0], [0, 0, 0], 0.25)
+            .grid_positions
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "dense grid would exceed")]
+    fn to_dense_rejects_a_grid_spanning_the_whole_range() {
+        let voxels = Voxels {
+            grid_positions: [[i32::MIN; 3], [i32::MAX; 3]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        voxels.to_dense();
+    }
+
+    #[test]
+    #[should_panic(expected = "nx * ny * nz")]
+    fn from_dense_rejects_dims_overflowing_usize() {
+        Voxels::from_dense(&[true], [usize::MAX, 2, 1], [0, 0, 0], 0.1);
+    }
+
+    #[test]
+    fn try_into_dense_rejects_two_far_apart_voxels() {
+        let far = Voxels {
+            grid_positions: [[0, 0, 0], [2000, 2000, 2000]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        assert_eq!(
+            far.try_into_dense(1 << 30).unwrap_err(),
+            VoxelizeError::TooManyCells { max_cells: 1 << 30 }
+        );
+        let whole = Voxels {
+            grid_positions: [[i32::MIN; 3], [i32::MAX; 3]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        assert!(whole.try_into_dense(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn try_into_dense_with_buffer_reuses_the_allocation() {
+        let mut voxels = Voxels::with_capacity(0, 0.1);
+        let mut buffer = Vec::with_capacity(64);
+        let capacity = buffer.capacity();
+        let pointer = buffer.as_ptr();
+        for n in 1..8 {
+            voxels.grid_positions.insert([n - 1, 0, 0]);
+            voxels.grid_positions.insert([0, n - 1, n - 1]);
+            let grid = voxels.try_into_dense_with_buffer(1000, buffer).unwrap();
+            assert_eq!(grid.count_ones(), voxels.grid_positions.len());
+            assert!(voxels.grid_positions.iter().all(|pos| grid.get(*pos)));
+            buffer = grid.into_words();
+            assert_eq!((buffer.as_ptr(), buffer.capacity()), (pointer, capacity));
+        }
+        // Stale words from a larger grid are cleared.
+        let one = Voxels {
+            grid_positions: [[0, 0, 0]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        assert_eq!(
+            one.try_into_dense_with_buffer(1000, buffer)
+                .unwrap()
+                .count_ones(),
+            1
+        );
+    }
+}
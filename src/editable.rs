@@ -0,0 +1,112 @@
+use super::voxelize::Voxels;
+use num_traits::Float;
+use std::cell::Cell;
+
+/// Voxels edited one cell at a time, with a bounding box kept up to date instead of rescanned.
+///
+/// `Voxels::grid_positions` is a public set that can change behind any cache, so the cache lives in this wrapper,
+/// which only allows edits through `insert` and `remove`.
+pub struct EditableVoxels<T: Float> {
+    voxels: Voxels<T>,
+    bounds: Cell<([i32; 3], [i32; 3])>,
+    stale: Cell<bool>,
+}
+
+impl<T: Float> EditableVoxels<T> {
+    /// Wraps voxels, scanning them once for the bounding box.
+    pub fn new(voxels: Voxels<T>) -> Self {
+        let bounds = voxels.min_max();
+        Self {
+            voxels,
+            bounds: Cell::new(bounds),
+            stale: Cell::new(false),
+        }
+    }
+    /// Gets the wrapped voxels.
+    #[inline]
+    pub fn voxels(&self) -> &Voxels<T> {
+        &self.voxels
+    }
+    /// Unwraps the voxels.
+    #[inline]
+    pub fn into_voxels(self) -> Voxels<T> {
+        self.voxels
+    }
+    /// Adds a cell and grows the bounding box to contain it. Returns whether the cell was new.
+    pub fn insert(&mut self, pos: [i32; 3]) -> bool {
+        if !self.voxels.grid_positions.insert(pos) {
+            return false;
+        }
+        if !self.stale.get() {
+            let (mut min, mut max) = self.bounds.get();
+            for axis in 0..3 {
+                min[axis] = min[axis].min(pos[axis]);
+                max[axis] = max[axis].max(pos[axis]);
+            }
+            self.bounds.set((min, max));
+        }
+        true
+    }
+    /// Removes a cell. Returns whether it was present.
+    ///
+    /// Removing a cell on the boundary marks the bounding box stale; it is rescanned by the next `min_max`.
+    pub fn remove(&mut self, pos: [i32; 3]) -> bool {
+        if !self.voxels.grid_positions.remove(&pos) {
+            return false;
+        }
+        let (min, max) = self.bounds.get();
+        if (0..3).any(|axis| pos[axis] == min[axis] || pos[axis] == max[axis]) {
+            self.stale.set(true);
+        }
+        true
+    }
+    /// Gets the same bounds as `Voxels::min_max`, rescanning only after a boundary cell was removed.
+    pub fn min_max(&self) -> ([i32; 3], [i32; 3]) {
+        if self.stale.get() {
+            self.bounds.set(self.voxels.min_max());
+            self.stale.set(false);
+        }
+        self.bounds.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn cached_bounds_match_a_fresh_scan() {
+        let mut editable = EditableVoxels::new(Voxels {
+            grid_positions: HashSet::new(),
+            step: 0.1,
+        });
+        let edits = [
+            (true, [0, 0, 0]),
+            (true, [3, -2, 1]),
+            (true, [1, 1, 1]),
+            (true, [3, -2, 1]),
+            (false, [5, 5, 5]),
+            // On the boundary.
+            (false, [3, -2, 1]),
+            (true, [-4, 0, 7]),
+            // Inside the bounds.
+            (false, [1, 1, 1]),
+            (false, [-4, 0, 7]),
+            (false, [0, 0, 0]),
+            (true, [2, 2, 2]),
+        ];
+        for (insert, pos) in edits.iter() {
+            let expected = editable.voxels().grid_positions.contains(pos) != *insert;
+            let changed = if *insert {
+                editable.insert(*pos)
+            } else {
+                editable.remove(*pos)
+            };
+            assert_eq!(changed, expected);
+            assert_eq!(editable.min_max(), editable.voxels().min_max());
+        }
+        assert_eq!(editable.min_max(), ([2, 2, 2], [2, 2, 2]));
+        assert_eq!(editable.into_voxels().grid_positions.len(), 1);
+    }
+}
@@ -0,0 +1,394 @@
+use super::voxelize::{CellAnchor, Voxels};
+use num_traits::Float;
+use std::fmt::Write;
+use std::io;
+
+/// Largest model extent per axis in the MagicaVoxel format.
+const VOX_MAX_SIZE: i32 = 256;
+
+impl<T: Float> Voxels<T> {
+    /// Renders the exposed faces as a Wavefront OBJ document, for callers that cannot write files (e.g. WASM).
+    ///
+    /// Vertices are written as in `vertices_indices`, so there are three `v` lines per `f` line.
+    pub fn to_obj_string(&self) -> String {
+        let (vertices, indices) = self.vertices_indices();
+        let mut obj = String::new();
+        for vertex in vertices {
+            let [x, y, z] = vertex.map(|c| c.to_f64().unwrap());
+            writeln!(obj, "v {:0.6} {:0.6} {:0.6}", x, y, z).unwrap();
+        }
+        for index in indices.chunks(3) {
+            writeln!(obj, "f {} {} {}", index[0] + 1, index[1] + 1, index[2] + 1).unwrap();
+        }
+        obj
+    }
+    /// Renders the exposed faces as a binary STL document.
+    #[cfg(feature = "stl")]
+    pub fn to_stl_bytes(&self) -> Vec<u8> {
+        let triangles = self.triangles();
+        let mut stl = Vec::with_capacity(84 + 50 * triangles.len());
+        stl.extend_from_slice(&[0u8; 80]);
+        stl.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for tri in triangles {
+            let [a, b, c] = tri.map(|p| p.map(|v| v.to_f32().unwrap()));
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let n = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            let normal = if len > 0.0 { n.map(|c| c / len) } else { n };
+            for p in [normal, a, b, c].iter() {
+                for v in p.iter() {
+                    stl.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+            stl.extend_from_slice(&0u16.to_le_bytes());
+        }
+        stl
+    }
+    /// Writes the voxels as a MagicaVoxel `.vox` model, shifted so that the minimum corner of `min_max` is at the origin.
+    ///
+    /// Every voxel takes color index 1, i.e. the first entry of `palette`. Without a palette, MagicaVoxel
+    /// uses its default one. Fails with `InvalidInput` if the voxels span more than 256 cells along an axis,
+    /// the format's limit, or if the palette has more than 256 entries.
+    pub fn write_vox<W: io::Write>(
+        &self,
+        w: &mut W,
+        palette: Option<&[[u8; 4]]>,
+    ) -> io::Result<()> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_string());
+        let (min, size) = if self.grid_positions.is_empty() {
+            ([0; 3], [1; 3])
+        } else {
+            let (min, max) = self.min_max();
+            let size = [
+                i64::from(max[0]) - i64::from(min[0]) + 1,
+                i64::from(max[1]) - i64::from(min[1]) + 1,
+                i64::from(max[2]) - i64::from(min[2]) + 1,
+            ];
+            if size.iter().any(|s| *s > i64::from(VOX_MAX_SIZE)) {
+                return Err(invalid("voxels span more than 256 cells along an axis"));
+            }
+            (min, size.map(|s| s as i32))
+        };
+        if palette.map_or(0, |palette| palette.len()) > 256 {
+            return Err(invalid("palette has more than 256 colors"));
+        }
+
+        let mut positions = self.grid_positions.iter().cloned().collect::<Vec<_>>();
+        positions.sort_unstable();
+        let mut size_chunk = Vec::with_capacity(12);
+        for s in size.iter() {
+            size_chunk.extend_from_slice(&s.to_le_bytes());
+        }
+        let mut xyzi_chunk = Vec::with_capacity(4 + 4 * positions.len());
+        xyzi_chunk.extend_from_slice(&(positions.len() as u32).to_le_bytes());
+        for pos in positions {
+            for axis in 0..3 {
+                xyzi_chunk.push((pos[axis] - min[axis]) as u8);
+            }
+            xyzi_chunk.push(1);
+        }
+        let mut children = Vec::new();
+        write_vox_chunk(&mut children, b"SIZE", &size_chunk);
+        write_vox_chunk(&mut children, b"XYZI", &xyzi_chunk);
+        if let Some(palette) = palette {
+            let mut rgba_chunk = vec![0u8; 4 * 256];
+            for (entry, color) in rgba_chunk.chunks_mut(4).zip(palette.iter()) {
+                entry.copy_from_slice(color);
+            }
+            write_vox_chunk(&mut children, b"RGBA", &rgba_chunk);
+        }
+
+        w.write_all(b"VOX ")?;
+        w.write_all(&150u32.to_le_bytes())?;
+        w.write_all(b"MAIN")?;
+        w.write_all(&0u32.to_le_bytes())?;
+        w.write_all(&(children.len() as u32).to_le_bytes())?;
+        w.write_all(&children)
+    }
+    /// Writes the exposed faces as a binary little-endian PLY mesh, with the vertices welded as in
+    /// `welded_vertices_indices(true)`.
+    ///
+    /// Faces are wound like `vertices_indices` and carry their outward normal as `nx`, `ny` and `nz`.
+    /// Everything is written straight to `w`, which is best wrapped in a `BufWriter`.
+    pub fn write_ply_mesh<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        let (vertices, indices) = self.welded_vertices_indices(true);
+        write!(
+            w,
+            "ply\nformat binary_little_endian 1.0\nelement vertex {}\n\
+             property float x\nproperty float y\nproperty float z\n\
+             element face {}\nproperty list uchar uint vertex_indices\n\
+             property float nx\nproperty float ny\nproperty float nz\nend_header\n",
+            vertices.len(),
+            indices.len() / 3
+        )?;
+        let vertices = vertices
+            .into_iter()
+            .map(|v| v.map(|c| c.to_f32().unwrap()))
+            .collect::<Vec<_>>();
+        for v in vertices.iter() {
+            write_ply_floats(w, v)?;
+        }
+        for index in indices.chunks(3) {
+            w.write_all(&[3])?;
+            for i in index {
+                w.write_all(&(*i as u32).to_le_bytes())?;
+            }
+            // The faces are wound clockwise seen from outside, so the right-hand normal points inwards.
+            let [a, b, c] = [vertices[index[0]], vertices[index[1]], vertices[index[2]]];
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let inward = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let len =
+                (inward[0] * inward[0] + inward[1] * inward[1] + inward[2] * inward[2]).sqrt();
+            write_ply_floats(w, &inward.map(|c| -c / len))?;
+        }
+        Ok(())
+    }
+    /// Writes the minimum corner of every voxel, as in `point_cloud`, as a binary little-endian PLY point cloud.
+    ///
+    /// Everything is written straight to `w`, which is best wrapped in a `BufWriter`.
+    pub fn write_ply_points<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_ply_points_anchored(w, CellAnchor::MinCorner)
+    }
+    /// Writes the anchor point of every voxel, as in `point_cloud_anchored`, as a binary little-endian PLY point cloud.
+    pub fn write_ply_points_anchored<W: io::Write>(
+        &self,
+        w: &mut W,
+        anchor: CellAnchor,
+    ) -> io::Result<()> {
+        write!(
+            w,
+            "ply\nformat binary_little_endian 1.0\nelement vertex {}\n\
+             property float x\nproperty float y\nproperty float z\nend_header\n",
+            self.grid_positions.len()
+        )?;
+        for pos in self.grid_positions.iter() {
+            let p = self.grid_to_world(*pos, anchor);
+            write_ply_floats(w, &p.map(|c| c.to_f32().unwrap()))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_ply_floats<W: io::Write>(w: &mut W, values: &[f32; 3]) -> io::Result<()> {
+    for v in values.iter() {
+        w.write_all(&v.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Appends a chunk without children: its id, content size, a zero children size and the content.
+fn write_vox_chunk(out: &mut Vec<u8>, id: &[u8; 4], content: &[u8]) {
+    out.extend_from_slice(id);
+    out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(content);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Voxels with a step of 0.5 occupying `cells`.
+    fn from_cells<I: IntoIterator<Item = [i32; 3]>>(cells: I) -> Voxels<f64> {
+        Voxels {
+            grid_positions: cells.into_iter().collect(),
+            step: 0.5,
+        }
+    }
+
+    /// Splits a PLY document into its header lines and its binary body.
+    fn split_ply(ply: &[u8]) -> (Vec<String>, &[u8]) {
+        let end = b"end_header\n";
+        let at = ply
+            .windows(end.len())
+            .position(|w| w == end)
+            .expect("PLY should have a header")
+            + end.len();
+        let header = std::str::from_utf8(&ply[..at]).unwrap();
+        (header.lines().map(str::to_string).collect(), &ply[at..])
+    }
+
+    #[test]
+    fn ply_points_follow_the_anchor() {
+        let voxels = from_cells(vec![[0, 0, 0], [1, -2, 3]]);
+        for anchor in [CellAnchor::MinCorner, CellAnchor::Center].iter() {
+            let mut ply = Vec::new();
+            voxels.write_ply_points_anchored(&mut ply, *anchor).unwrap();
+            let (header, body) = split_ply(&ply);
+            assert!(header.contains(&"element vertex 2".to_string()));
+            let floats = body
+                .chunks(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect::<Vec<_>>();
+            let expected = voxels
+                .grid_iter()
+                .flat_map(|pos| voxels.grid_to_world(pos, *anchor))
+                .map(|c| c as f32)
+                .collect::<Vec<_>>();
+            assert_eq!(floats, expected);
+        }
+    }
+
+    #[test]
+    fn obj_string_has_a_line_per_vertex_and_triangle() {
+        let voxels = from_cells(vec![[0, 0, 0], [1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+        let (vertices, indices) = voxels.vertices_indices();
+        let obj = voxels.to_obj_string();
+        let count = |prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(count("v "), vertices.len());
+        assert_eq!(count("f "), indices.len() / 3);
+        assert_eq!(obj.lines().count(), vertices.len() + indices.len() / 3);
+        // Three sides of the corner cell are covered, and every other face is exposed.
+        assert_eq!(count("f "), (4 * 6 - 6) * 2);
+        assert!(obj.lines().any(|l| l == "f 1 2 3"));
+    }
+
+    #[cfg(feature = "stl")]
+    #[test]
+    fn stl_bytes_hold_every_triangle() {
+        let voxels = from_cells(vec![[0, 0, 0], [1, 0, 0]]);
+        let stl = voxels.to_stl_bytes();
+        assert_eq!(stl.len(), 84 + 50 * 20);
+        assert_eq!(u32::from_le_bytes([stl[80], stl[81], stl[82], stl[83]]), 20);
+    }
+
+    /// Reads the chunks of a `.vox` document after its `MAIN` header, as ids and contents.
+    fn vox_chunks(vox: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        let u32_at =
+            |at: usize| u32::from_le_bytes([vox[at], vox[at + 1], vox[at + 2], vox[at + 3]]);
+        assert_eq!(&vox[..4], b"VOX ");
+        assert_eq!(u32_at(4), 150);
+        assert_eq!(&vox[8..12], b"MAIN");
+        assert_eq!(u32_at(12), 0);
+        assert_eq!(u32_at(16) as usize, vox.len() - 20);
+        let mut chunks = Vec::new();
+        let mut at = 20;
+        while at < vox.len() {
+            let mut id = [0; 4];
+            id.copy_from_slice(&vox[at..at + 4]);
+            let size = u32_at(at + 4) as usize;
+            assert_eq!(u32_at(at + 8), 0);
+            chunks.push((id, vox[at + 12..at + 12 + size].to_vec()));
+            at += 12 + size;
+        }
+        assert_eq!(at, vox.len());
+        chunks
+    }
+
+    #[test]
+    fn vox_chunks_hold_the_size_and_every_voxel() {
+        let voxels = from_cells(vec![[-3, 5, 0], [-2, 5, 0], [-3, 7, 1]]);
+        let mut vox = Vec::new();
+        voxels.write_vox(&mut vox, None).unwrap();
+        let chunks = vox_chunks(&vox);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(&chunks[0].0, b"SIZE");
+        let size = chunks[0]
+            .1
+            .chunks(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect::<Vec<_>>();
+        assert_eq!(size, vec![2, 3, 2]);
+        assert_eq!(&chunks[1].0, b"XYZI");
+        let xyzi = &chunks[1].1;
+        assert_eq!(
+            u32::from_le_bytes([xyzi[0], xyzi[1], xyzi[2], xyzi[3]]) as usize,
+            voxels.grid_positions.len()
+        );
+        assert_eq!(&xyzi[4..], &[0, 0, 0, 1, 0, 2, 1, 1, 1, 0, 0, 1][..]);
+
+        let palette = [[255, 0, 0, 255], [0, 255, 0, 255]];
+        let mut vox = Vec::new();
+        voxels.write_vox(&mut vox, Some(&palette)).unwrap();
+        let chunks = vox_chunks(&vox);
+        assert_eq!(&chunks[2].0, b"RGBA");
+        assert_eq!(chunks[2].1.len(), 1024);
+        assert_eq!(&chunks[2].1[..8], &[255, 0, 0, 255, 0, 255, 0, 255][..]);
+    }
+
+    #[test]
+    fn vox_rejects_more_than_256_cells_along_an_axis() {
+        let mut vox = Vec::new();
+        assert!(from_cells(vec![[0, 0, 0], [0, 255, 0]])
+            .write_vox(&mut vox, None)
+            .is_ok());
+        let error = from_cells(vec![[0, 0, 0], [0, 256, 0]])
+            .write_vox(&mut Vec::new(), None)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let error = from_cells(vec![[i32::MIN, 0, 0], [i32::MAX, 0, 0]])
+            .write_vox(&mut Vec::new(), None)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let palette = vec![[0; 4]; 257];
+        assert!(from_cells(vec![[0, 0, 0]])
+            .write_vox(&mut Vec::new(), Some(&palette))
+            .is_err());
+    }
+
+    #[test]
+    fn ply_mesh_header_states_the_welded_counts() {
+        let voxels = from_cells(vec![[0, 0, 0], [1, 0, 0]]);
+        let mut ply = Vec::new();
+        voxels.write_ply_mesh(&mut ply).unwrap();
+        let (header, body) = split_ply(&ply);
+        assert_eq!(header[0], "ply");
+        assert_eq!(header[1], "format binary_little_endian 1.0");
+        // Two cubes sharing a face weld into 12 corners and expose 10 faces.
+        assert!(header.contains(&"element vertex 12".to_string()));
+        assert!(header.contains(&"element face 20".to_string()));
+        assert_eq!(header.last().unwrap(), "end_header");
+        // Three floats per vertex, then a count, three indices and three normal floats per face.
+        assert_eq!(body.len(), 12 * 12 + 20 * (1 + 12 + 12));
+        let floats = |bytes: &[u8]| {
+            bytes
+                .chunks(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect::<Vec<_>>()
+        };
+        let vertices = floats(&body[..12 * 12]);
+        // The two cubes of `vertices_indices` are centered at 0 and 0.5 along x.
+        let center = [0.25, 0.0, 0.0];
+        for face in body[12 * 12..].chunks(25) {
+            assert_eq!(face[0], 3);
+            let corners = face[1..13]
+                .chunks(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+                .collect::<Vec<_>>();
+            let normal = floats(&face[13..]);
+            assert_eq!(normal.iter().filter(|c| c.abs() == 1.0).count(), 1);
+            assert_eq!(normal.iter().filter(|c| **c == 0.0).count(), 2);
+            // Pointing out of the box, away from its center.
+            let outward = (0..3)
+                .map(|axis| {
+                    let centroid =
+                        corners.iter().map(|i| vertices[3 * i + axis]).sum::<f32>() / 3.0;
+                    normal[axis] * (centroid - center[axis])
+                })
+                .sum::<f32>();
+            assert!(outward > 0.0);
+        }
+    }
+
+    #[test]
+    fn ply_points_hold_every_voxel() {
+        let voxels = from_cells((0..5).map(|x| [x, -x, 2 * x]));
+        let mut ply = Vec::new();
+        voxels.write_ply_points(&mut ply).unwrap();
+        let (header, body) = split_ply(&ply);
+        assert!(header.contains(&"element vertex 5".to_string()));
+        assert!(!header.iter().any(|line| line.starts_with("element face")));
+        assert_eq!(body.len(), 5 * 12);
+    }
+}
@@ -0,0 +1,85 @@
+use super::vector::Vector3;
+use super::voxelize::{build_triangles, default_eps, Triangle, Voxels};
+use num_traits::Float;
+use std::collections::{HashMap, HashSet};
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes a mesh and also reports the cells touched by its sharp feature edges.
+    ///
+    /// An edge is sharp when the normals of the two triangles sharing it differ by more than `angle_threshold` radians.
+    /// Boundary edges and edges shared by more than two triangles are not reported.
+    pub fn voxelize_with_feature_edges(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        step: T,
+        angle_threshold: T,
+    ) -> (Voxels<T>, HashSet<[i32; 3]>)
+    where
+        T: Send + Sync,
+    {
+        let voxels = Voxels::voxelize(vertices, indices, step);
+        let tris = build_triangles(vertices, indices);
+
+        let mut edge_tris: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (t, index) in indices.chunks(3).enumerate() {
+            for i in 0..3 {
+                let (a, b) = (index[i], index[(i + 1) % 3]);
+                edge_tris.entry((a.min(b), a.max(b))).or_default().push(t);
+            }
+        }
+
+        let normal = |tri: &Triangle<T>| {
+            let n = (tri.points[1] - tri.points[0]).cross(&(tri.points[2] - tri.points[0]));
+            let len = n.dot(&n).sqrt();
+            if len > T::zero() {
+                n / len
+            } else {
+                n
+            }
+        };
+        let cos_threshold = angle_threshold.cos();
+        let eps = default_eps();
+        let mut feature_cells = HashSet::new();
+        for ((a, b), faces) in edge_tris.iter() {
+            if faces.len() != 2 {
+                continue;
+            }
+            if normal(&tris[faces[0]]).dot(&normal(&tris[faces[1]])) >= cos_threshold {
+                continue;
+            }
+            let p = Vector3::new(vertices[*a][0], vertices[*a][1], vertices[*a][2]);
+            let q = Vector3::new(vertices[*b][0], vertices[*b][1], vertices[*b][2]);
+            // A triangle collapsed onto the edge is tested against each cell as a segment.
+            let segment = Triangle::new(&p, &q, &q);
+            feature_cells.extend(segment.scan(step, eps));
+        }
+        (voxels, feature_cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::cube;
+
+    #[test]
+    fn cube_edges_are_feature_edges() {
+        let (vertices, indices) = cube(0.47);
+        let (voxels, edges) =
+            Voxels::voxelize_with_feature_edges(&vertices, &indices, 0.1, 45f64.to_radians());
+        assert_eq!(voxels, Voxels::voxelize(&vertices, &indices, 0.1));
+        // The cube spans cells -5 to 4, and its edges touch the cells with two coordinates at either end.
+        let expected = voxels
+            .grid_positions
+            .iter()
+            .filter(|pos| pos.iter().filter(|c| **c == -5 || **c == 4).count() >= 2)
+            .cloned()
+            .collect::<HashSet<_>>();
+        assert_eq!(expected.len(), 12 * 10 - 8 * 2);
+        assert_eq!(edges, expected);
+
+        let (_, edges) =
+            Voxels::voxelize_with_feature_edges(&vertices, &indices, 0.1, 100f64.to_radians());
+        assert!(edges.is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+use super::voxelize::Voxels;
+use num_traits::Float;
+use std::collections::HashMap;
+
+/// A directed edge of one triangle of a `HalfEdgeMesh`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalfEdge {
+    /// The vertex the edge starts from.
+    pub origin: usize,
+    /// The opposite edge of the neighboring triangle, or `None` on a boundary or a non-manifold edge.
+    pub twin: Option<usize>,
+}
+
+/// A triangle mesh with face adjacency, built by `Voxels::half_edge_mesh`.
+///
+/// The half-edges of triangle `f` are `3 * f`, `3 * f + 1` and `3 * f + 2`, in winding order,
+/// so the next edge and the face of an edge follow from its index.
+#[derive(Debug, Clone)]
+pub struct HalfEdgeMesh<T> {
+    /// The welded vertices.
+    pub vertices: Vec<[T; 3]>,
+    /// Three half-edges per triangle.
+    pub half_edges: Vec<HalfEdge>,
+}
+
+impl<T> HalfEdgeMesh<T> {
+    /// Gets the number of triangles.
+    #[inline]
+    pub fn face_count(&self) -> usize {
+        self.half_edges.len() / 3
+    }
+    /// Gets the triangle a half-edge belongs to.
+    #[inline]
+    pub fn face(&self, half_edge: usize) -> usize {
+        half_edge / 3
+    }
+    /// Gets the half-edge following `half_edge` around its triangle.
+    #[inline]
+    pub fn next(&self, half_edge: usize) -> usize {
+        half_edge - half_edge % 3 + (half_edge + 1) % 3
+    }
+    /// Gets the vertex a half-edge ends at.
+    #[inline]
+    pub fn target(&self, half_edge: usize) -> usize {
+        self.half_edges[self.next(half_edge)].origin
+    }
+    /// Iterates over the triangles sharing an edge with `face`.
+    pub fn face_neighbors(&self, face: usize) -> impl Iterator<Item = usize> + '_ {
+        (3 * face..3 * face + 3).filter_map(move |e| self.half_edges[e].twin.map(|t| t / 3))
+    }
+    /// Checks whether every half-edge has a twin, i.e. the surface is closed and every edge joins exactly
+    /// two triangles.
+    pub fn is_closed_manifold(&self) -> bool {
+        self.half_edges.iter().all(|e| e.twin.is_some())
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Builds a half-edge mesh of the exposed faces, welded as `welded_vertices_indices(true)`.
+    ///
+    /// Voxels touching only along an edge make that edge shared by four triangles. Such edges, like boundary
+    /// edges, get no twin, so `is_closed_manifold` reports them.
+    pub fn half_edge_mesh(&self) -> HalfEdgeMesh<T> {
+        let (vertices, indices) = self.welded_vertices_indices(true);
+        let mut half_edges = Vec::with_capacity(indices.len());
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (i, origin) in indices.iter().enumerate() {
+            let target = indices[i - i % 3 + (i + 1) % 3];
+            half_edges.push(HalfEdge {
+                origin: *origin,
+                twin: None,
+            });
+            edges.entry((*origin, target)).or_default().push(i);
+        }
+        for ((origin, target), here) in edges.iter() {
+            if let (1, Some(there)) = (here.len(), edges.get(&(*target, *origin))) {
+                if there.len() == 1 {
+                    half_edges[here[0]].twin = Some(there[0]);
+                }
+            }
+        }
+        HalfEdgeMesh {
+            vertices,
+            half_edges,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_cells<I: IntoIterator<Item = [i32; 3]>>(cells: I) -> Voxels<f64> {
+        Voxels {
+            grid_positions: cells.into_iter().collect(),
+            step: 0.5,
+        }
+    }
+
+    /// Checks that every twin points back and runs the opposite way.
+    fn assert_twins_consistent(mesh: &HalfEdgeMesh<f64>) {
+        for (e, edge) in mesh.half_edges.iter().enumerate() {
+            if let Some(t) = edge.twin {
+                assert_eq!(mesh.half_edges[t].twin, Some(e));
+                assert_eq!(mesh.half_edges[t].origin, mesh.target(e));
+                assert_eq!(mesh.target(t), edge.origin);
+                assert_ne!(mesh.face(t), mesh.face(e));
+            }
+        }
+    }
+
+    #[test]
+    fn cube_is_a_closed_manifold_with_consistent_twins() {
+        let mut cells = Vec::new();
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    cells.push([x, y, z]);
+                }
+            }
+        }
+        let mesh = from_cells(cells).half_edge_mesh();
+        // Each side is 2 by 2 faces sharing a 3 by 3 lattice of vertices.
+        assert_eq!(mesh.face_count(), 6 * 4 * 2);
+        assert_eq!(mesh.vertices.len(), 26);
+        assert!(mesh.is_closed_manifold());
+        assert_twins_consistent(&mesh);
+        for face in 0..mesh.face_count() {
+            assert_eq!(mesh.face_neighbors(face).count(), 3);
+        }
+        // Euler characteristic of a sphere.
+        let edges = mesh.half_edges.len() / 2;
+        assert_eq!(mesh.vertices.len() + mesh.face_count() - edges, 2);
+    }
+
+    #[test]
+    fn cells_sharing_only_an_edge_are_not_manifold() {
+        let mesh = from_cells(vec![[0, 0, 0], [1, 1, 0]]).half_edge_mesh();
+        assert_eq!(mesh.face_count(), 24);
+        assert!(!mesh.is_closed_manifold());
+        assert_twins_consistent(&mesh);
+        // The four triangles along the shared edge have no twin there.
+        assert_eq!(
+            mesh.half_edges.iter().filter(|e| e.twin.is_none()).count(),
+            4
+        );
+    }
+}
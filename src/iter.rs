@@ -0,0 +1,76 @@
+use super::voxelize::{CellAnchor, Voxels};
+use num_traits::Float;
+use std::collections::hash_set;
+
+/// An iterator over the world-space minimum corners of the voxels, created by iterating over `&Voxels`.
+///
+/// It yields the points of `point_cloud` without collecting them. Like `grid_positions`, it visits the voxels
+/// in no particular order.
+#[derive(Debug, Clone)]
+pub struct Points<'a, T: Float> {
+    positions: hash_set::Iter<'a, [i32; 3]>,
+    voxels: &'a Voxels<T>,
+}
+
+impl<'a, T: Float> Iterator for Points<'a, T> {
+    type Item = [T; 3];
+    #[inline]
+    fn next(&mut self) -> Option<[T; 3]> {
+        let pos = self.positions.next()?;
+        Some(self.voxels.grid_to_world(*pos, CellAnchor::MinCorner))
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.positions.size_hint()
+    }
+}
+
+impl<'a, T: Float> ExactSizeIterator for Points<'a, T> {}
+
+impl<'a, T: Float> IntoIterator for &'a Voxels<T> {
+    type Item = [T; 3];
+    type IntoIter = Points<'a, T>;
+    fn into_iter(self) -> Points<'a, T> {
+        Points {
+            positions: self.grid_positions.iter(),
+            voxels: self,
+        }
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Iterates over the grid positions of the voxels, in no particular order.
+    pub fn grid_iter(&self) -> impl Iterator<Item = [i32; 3]> + '_ {
+        self.grid_positions.iter().cloned()
+    }
+    /// Iterates over the world-space boxes of the voxels as their minimum and maximum corners,
+    /// in the order of `grid_iter`.
+    pub fn world_boxes(&self) -> impl Iterator<Item = ([T; 3], [T; 3])> + '_ {
+        self.grid_positions.iter().map(move |pos| {
+            let min = self.grid_to_world(*pos, CellAnchor::MinCorner);
+            (
+                min,
+                [min[0] + self.step, min[1] + self.step, min[2] + self.step],
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::pyramid;
+
+    #[test]
+    fn iterating_gives_the_point_cloud() {
+        let (vertices, indices) = pyramid();
+        let voxels = Voxels::voxelize(&vertices, &indices, 0.1);
+        let mut iterated = voxels.into_iter().collect::<Vec<_>>();
+        let mut cloud = voxels.point_cloud();
+        assert_eq!(voxels.into_iter().len(), cloud.len());
+        let by_coordinates = |a: &[f64; 3], b: &[f64; 3]| a.partial_cmp(b).unwrap();
+        iterated.sort_by(by_coordinates);
+        cloud.sort_by(by_coordinates);
+        assert_eq!(iterated, cloud);
+    }
+}
@@ -3,7 +3,7 @@
 //!
 //! ## Example
 //!
-//! ```rust
+//! ```rust,no_run
 //! use meshvox::Voxels;
 //! use std::fs::File;
 //! use std::io::{BufWriter, Write};
@@ -51,9 +51,68 @@
 //! }
 //! ```
 
-pub(crate) mod sat;
-pub(crate) mod vector;
+/// Enters a `tracing` span until the end of the enclosing block when the `trace` feature is enabled.
+macro_rules! trace_span {
+    ($name:expr) => {
+        #[cfg(feature = "trace")]
+        let _span = tracing::debug_span!($name).entered();
+    };
+}
+
+mod adaptive;
+mod aniso;
+mod chunk;
+mod clip;
+mod components;
+mod dense;
+mod editable;
+mod export;
+mod features;
+mod greedy_mesh;
+mod halfedge;
+mod iter;
+mod measure;
+mod morphology;
+mod ops;
+mod palette;
+mod prep;
+mod raycast;
+mod sample;
+mod sat;
+mod sdf;
+mod sheet;
+mod slice;
+mod smooth;
+mod solid;
+#[cfg(test)]
+mod testing;
+mod transform;
+mod vector;
 pub mod voxelize;
-//pub(crate) mod greedy_mesh;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod wide;
 
+pub use adaptive::AdaptiveVoxels;
+pub use aniso::AnisoVoxels;
+pub use chunk::voxelize_to_chunks;
+pub use clip::ClipMode;
+pub use components::Connectivity;
+pub use dense::{BitGrid, DenseGrid};
+pub use editable::EditableVoxels;
+pub use halfedge::{HalfEdge, HalfEdgeMesh};
+pub use iter::Points;
+pub use measure::{analyze_mesh, mesh_signed_volume, suggest_step, suggest_step_for_count, MeshStats};
+pub use palette::distinct_colors;
+pub use prep::{
+    remove_duplicate_triangles, remove_out_of_range_triangles, remove_repeated_vertex_triangles,
+};
+pub use raycast::RayHit;
+pub use sat::triangle_aabb_intersects;
+pub use sheet::{FacingSide, SheetNormals, SheetVoxels};
+pub use solid::SolidRule;
+pub use vector::Vector3;
 pub use voxelize::*;
+#[cfg(feature = "wasm")]
+pub use wasm::{voxelize_flat, voxelize_wasm};
+pub use wide::Voxels64;
@@ -0,0 +1,375 @@
+use super::voxelize::{build_triangles, CellAnchor, Voxels};
+use num_traits::Float;
+use std::collections::HashMap;
+
+/// Computes the signed volume enclosed by a closed triangle mesh using the divergence theorem.
+///
+/// The result is positive when the triangles are wound counter-clockwise seen from outside.
+pub fn mesh_signed_volume<T: Float>(vertices: &[[T; 3]], indices: &[usize]) -> T {
+    let six = T::from(6).unwrap();
+    indices.chunks(3).fold(T::zero(), |volume, index| {
+        let a = vertices[index[0]];
+        let b = vertices[index[1]];
+        let c = vertices[index[2]];
+        let det = a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+            + a[2] * (b[0] * c[1] - b[1] * c[0]);
+        volume + det / six
+    })
+}
+
+/// Gets the bounding box of the vertices referenced by `indices`, or `None` if there are none.
+pub(crate) fn mesh_bounds<T: Float>(
+    vertices: &[[T; 3]],
+    indices: &[usize],
+) -> Option<([T; 3], [T; 3])> {
+    indices.iter().fold(None, |bounds, i| {
+        let v = vertices[*i];
+        Some(match bounds {
+            None => (v, v),
+            Some((min, max)) => (
+                [min[0].min(v[0]), min[1].min(v[1]), min[2].min(v[2])],
+                [max[0].max(v[0]), max[1].max(v[1]), max[2].max(v[2])],
+            ),
+        })
+    })
+}
+
+/// Suggests a step so that the longest side of the mesh bounding box spans about `target_resolution` voxels.
+///
+/// Returns zero for a mesh without extent, which `Voxels::voxelize` rejects.
+pub fn suggest_step<T: Float>(vertices: &[[T; 3]], indices: &[usize], target_resolution: u32) -> T {
+    match mesh_bounds(vertices, indices) {
+        Some((min, max)) => {
+            let longest = (max[0] - min[0]).max(max[1] - min[1]).max(max[2] - min[2]);
+            longest / T::from(target_resolution.max(1)).unwrap()
+        }
+        None => T::zero(),
+    }
+}
+
+/// Suggests a step so that `Voxels::voxelize` marks about `target_voxels` cells on the surface of the mesh.
+///
+/// A surface of area `A` crosses roughly `1.5 * A / step²` cells, the factor being the mean of `|nx| + |ny| + |nz|`
+/// over all orientations of its normal, so the estimate is good to a few tens of percent for curved meshes
+/// while meshes aligned with the axes get up to a third fewer cells. Returns zero for a mesh without area.
+pub fn suggest_step_for_count<T: Float>(
+    vertices: &[[T; 3]],
+    indices: &[usize],
+    target_voxels: usize,
+) -> T {
+    let area = analyze_mesh(vertices, indices).surface_area;
+    (T::from(1.5).unwrap() * area / T::from(target_voxels.max(1)).unwrap()).sqrt()
+}
+
+/// Summary of a triangle mesh, for judging how well a step samples it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshStats<T> {
+    /// Number of triangles.
+    pub triangle_count: usize,
+    /// Sum of the triangle areas.
+    pub surface_area: T,
+    /// Shortest triangle edge.
+    pub min_edge_length: T,
+    /// Longest triangle edge.
+    pub max_edge_length: T,
+    /// Mean length over the three edges of every triangle, so shared edges count once per triangle.
+    pub mean_edge_length: T,
+    /// Bounding box of the referenced vertices, or `None` for an empty mesh.
+    pub bounds: Option<([T; 3], [T; 3])>,
+}
+
+/// Measures a mesh. Comparing `mean_edge_length` with the step tells whether the grid under- or over-samples it.
+///
+/// Edge lengths are zero for an empty mesh.
+pub fn analyze_mesh<T: Float>(vertices: &[[T; 3]], indices: &[usize]) -> MeshStats<T> {
+    let tris = build_triangles(vertices, indices);
+    let two = T::one() + T::one();
+    let mut surface_area = T::zero();
+    let mut min_edge_length = T::infinity();
+    let mut max_edge_length = T::zero();
+    let mut edge_length_sum = T::zero();
+    for tri in tris.iter() {
+        let [p0, p1, p2] = tri.points;
+        let n = (p1 - p0).cross(&(p2 - p0));
+        surface_area = surface_area + n.dot(&n).sqrt() / two;
+        for (a, b) in [(p0, p1), (p1, p2), (p2, p0)].iter() {
+            let length = (*b - *a).dot(&(*b - *a)).sqrt();
+            min_edge_length = min_edge_length.min(length);
+            max_edge_length = max_edge_length.max(length);
+            edge_length_sum = edge_length_sum + length;
+        }
+    }
+    let (min_edge_length, mean_edge_length) = if tris.is_empty() {
+        (T::zero(), T::zero())
+    } else {
+        (
+            min_edge_length,
+            edge_length_sum / T::from(3 * tris.len()).unwrap(),
+        )
+    };
+    MeshStats {
+        triangle_count: tris.len(),
+        surface_area,
+        min_edge_length,
+        max_edge_length,
+        mean_edge_length,
+        bounds: mesh_bounds(vertices, indices),
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes a mesh with the step given by `suggest_step`, so that the longest side of its bounding box
+    /// spans about `max_dim_voxels` cells. Depending on where the mesh falls on the grid, it may take one more.
+    ///
+    /// Panics like `voxelize` if all referenced vertices coincide, since no step fits a mesh without extent.
+    pub fn voxelize_to_resolution(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        max_dim_voxels: u32,
+    ) -> Self
+    where
+        T: Send + Sync,
+    {
+        let step = suggest_step(vertices, indices, max_dim_voxels);
+        Voxels::voxelize(vertices, indices, step)
+    }
+    /// Gets the relative error `(voxel_volume - mesh_volume) / mesh_volume` against the volume of the source mesh.
+    ///
+    /// Only meaningful for solid voxels, i.e. after `fill`. See `mesh_signed_volume`.
+    pub fn volume_error_vs(&self, mesh_signed_volume: T) -> T {
+        (self.volume() - mesh_signed_volume) / mesh_signed_volume
+    }
+    /// Gets the total volume of the voxels, `step³` per voxel.
+    ///
+    /// Only meaningful as the volume of a solid object after `fill`; before that it measures the surface cells only.
+    pub fn volume(&self) -> T {
+        T::from(self.grid_positions.len()).unwrap() * self.step.powi(3)
+    }
+    /// Gets the total area of the exposed faces, `step²` per face, as meshed by `vertices_indices`.
+    ///
+    /// Cube faces follow the surface in steps, so this overestimates the area of a smooth surface
+    /// by up to 50% on average for random orientations and does not converge as `step` shrinks.
+    pub fn surface_area(&self) -> T {
+        let faces = self.face_counts().iter().sum::<usize>();
+        T::from(faces).unwrap() * self.step.powi(2)
+    }
+    /// Gets the world-space bounding box of the voxels, from the minimum corner of the lowest cells to the
+    /// maximum corner of the highest ones, or `None` if there are no voxels.
+    pub fn world_bounds(&self) -> Option<([T; 3], [T; 3])> {
+        if self.grid_positions.is_empty() {
+            return None;
+        }
+        let (min, max) = self.min_max();
+        let upper = [
+            T::from(max[0]).unwrap() + T::one(),
+            T::from(max[1]).unwrap() + T::one(),
+            T::from(max[2]).unwrap() + T::one(),
+        ];
+        Some((
+            self.grid_to_world(min, CellAnchor::MinCorner),
+            upper.map(|c| c * self.step),
+        ))
+    }
+    /// Gets the mean of the voxel centers in world space, or `None` if there are no voxels.
+    ///
+    /// After `fill`, this approximates the centroid of the solid, i.e. its center of mass at uniform density.
+    pub fn center_of_mass(&self) -> Option<[T; 3]> {
+        if self.grid_positions.is_empty() {
+            return None;
+        }
+        // Summed exactly, so the mean does not depend on the iteration order of the set.
+        let mut sum = [0i128; 3];
+        for pos in self.grid_positions.iter() {
+            for axis in 0..3 {
+                sum[axis] += i128::from(pos[axis]);
+            }
+        }
+        let count = T::from(self.grid_positions.len()).unwrap();
+        let half = T::from(0.5).unwrap();
+        Some(sum.map(|s| (T::from(s).unwrap() / count + half) * self.step))
+    }
+    /// Partitions the grid into super-cells of `block` cells and gets the fraction of occupied cells in each.
+    ///
+    /// Super-cell `[i, j, k]` covers the cells from `[i, j, k] * block` up to the next super-cell.
+    /// Every super-cell overlapping `min_max` is reported, empty ones as zero, which gives a low-resolution
+    /// porosity map. Panics if a component of `block` is not positive.
+    pub fn density_field(&self, block: [i32; 3]) -> HashMap<[i32; 3], T> {
+        if block.iter().any(|b| *b <= 0) {
+            panic!("block should be positive values");
+        }
+        let super_cell = |pos: [i32; 3]| {
+            [
+                pos[0].div_euclid(block[0]),
+                pos[1].div_euclid(block[1]),
+                pos[2].div_euclid(block[2]),
+            ]
+        };
+        let mut counts: HashMap<[i32; 3], usize> = HashMap::new();
+        if !self.grid_positions.is_empty() {
+            let (min, max) = self.min_max();
+            let (first, last) = (super_cell(min), super_cell(max));
+            for x in first[0]..=last[0] {
+                for y in first[1]..=last[1] {
+                    for z in first[2]..=last[2] {
+                        counts.insert([x, y, z], 0);
+                    }
+                }
+            }
+        }
+        for pos in self.grid_positions.iter() {
+            *counts.entry(super_cell(*pos)).or_default() += 1;
+        }
+        let volume =
+            T::from(block[0]).unwrap() * T::from(block[1]).unwrap() * T::from(block[2]).unwrap();
+        counts
+            .into_iter()
+            .map(|(cell, count)| (cell, T::from(count).unwrap() / volume))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{cube, sphere};
+
+    fn from_cells<I: IntoIterator<Item = [i32; 3]>>(cells: I) -> Voxels<f64> {
+        Voxels {
+            grid_positions: cells.into_iter().collect(),
+            step: 0.1,
+        }
+    }
+
+    /// The 2 by 2 by 1 block of cells from `[0, 0, 0]`.
+    fn flat_block() -> Voxels<f64> {
+        from_cells(vec![[0, 0, 0], [1, 0, 0], [0, 1, 0], [1, 1, 0]])
+    }
+
+    #[test]
+    fn volume_and_surface_area_of_a_flat_block() {
+        let block = flat_block();
+        // Four cells of 0.1³, and 4 faces on top, 4 below and 2 on each of the 4 sides, of 0.1² each.
+        assert!((block.volume() - 4e-3).abs() < 1e-15);
+        assert!((block.surface_area() - 0.16).abs() < 1e-15);
+        let empty = from_cells(vec![]);
+        assert_eq!((empty.volume(), empty.surface_area()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn world_bounds_and_center_of_mass_of_a_flat_block() {
+        let mut block = flat_block();
+        let close = |a: [f64; 3], b: [f64; 3]| (0..3).all(|i| (a[i] - b[i]).abs() < 1e-12);
+        let (lower, upper) = block.world_bounds().unwrap();
+        assert!(close(lower, [0.0, 0.0, 0.0]) && close(upper, [0.2, 0.2, 0.1]));
+        assert!(close(block.center_of_mass().unwrap(), [0.1, 0.1, 0.05]));
+
+        // Moved below the origin, the bounds still run from the lower corner of the lowest cells.
+        block.translate([-3, 0, -1]).unwrap();
+        let (lower, upper) = block.world_bounds().unwrap();
+        assert!(close(lower, [-0.3, 0.0, -0.1]) && close(upper, [-0.1, 0.2, 0.0]));
+        assert!(close(block.center_of_mass().unwrap(), [-0.2, 0.1, -0.05]));
+
+        let empty = from_cells(vec![]);
+        assert_eq!((empty.world_bounds(), empty.center_of_mass()), (None, None));
+    }
+
+    #[test]
+    fn volume_error_of_a_cube_shrinks_with_the_step() {
+        let (vertices, indices) = cube(0.47);
+        let mesh_volume = mesh_signed_volume(&vertices, &indices);
+        assert!((mesh_volume - 0.94f64.powi(3)).abs() < 1e-12);
+        // Steps putting the faces of the cube in the middle of a cell, so that the cube covers `2 * n + 2` cells
+        // per axis instead of `2 * n + 1`.
+        let errors = [2, 5, 11, 23]
+            .iter()
+            .map(|n| {
+                let cells = f64::from(2 * n + 1);
+                let step = 0.94 / cells;
+                let error =
+                    Voxels::voxelize_solid(&vertices, &indices, step).volume_error_vs(mesh_volume);
+                assert!((error - (((cells + 1.0) / cells).powi(3) - 1.0)).abs() < 1e-9);
+                error
+            })
+            .collect::<Vec<_>>();
+        assert!(errors.windows(2).all(|pair| pair[1] < pair[0]));
+    }
+
+    #[test]
+    fn suggest_step_divides_the_longest_side() {
+        let (vertices, indices) = cube(5.0);
+        assert!((suggest_step(&vertices, &indices, 100) - 0.1).abs() < 1e-12);
+        let flat = [[0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        assert_eq!(suggest_step(&flat, &[0, 1, 2], 8), 0.5);
+        assert_eq!(suggest_step(&flat, &[0, 0, 0], 8), 0.0);
+    }
+
+    #[test]
+    fn unit_cube_has_an_area_of_six() {
+        let (vertices, indices) = cube(0.5);
+        let stats = analyze_mesh(&vertices, &indices);
+        assert_eq!(stats.triangle_count, 12);
+        assert!((stats.surface_area - 6.0).abs() < 1e-12);
+        assert!((stats.min_edge_length - 1.0).abs() < 1e-12);
+        assert!((stats.max_edge_length - 2f64.sqrt()).abs() < 1e-12);
+        assert!((stats.mean_edge_length - (2.0 + 2f64.sqrt()) / 3.0).abs() < 1e-12);
+        assert_eq!(stats.bounds, Some(([-0.5; 3], [0.5; 3])));
+
+        let empty = analyze_mesh::<f64>(&[], &[]);
+        assert_eq!(empty.triangle_count, 0);
+        assert_eq!(empty.min_edge_length, 0.0);
+        assert_eq!(empty.bounds, None);
+    }
+
+    #[test]
+    fn voxelize_to_resolution_spans_the_requested_cells() {
+        let (vertices, indices) = sphere(1.3, 24, 12);
+        let flat = [[0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        for resolution in [10, 33, 64].iter() {
+            for (vertices, indices) in
+                [(&vertices[..], &indices[..]), (&flat[..], &[0, 1, 2][..])].iter()
+            {
+                let voxels = Voxels::voxelize_to_resolution(vertices, indices, *resolution);
+                let (min, max) = voxels.min_max();
+                let longest = (0..3).map(|axis| max[axis] - min[axis] + 1).max().unwrap();
+                assert!((longest - *resolution as i32).abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn suggest_step_for_count_gives_about_the_requested_cells() {
+        let (vertices, indices) = sphere(1.0, 48, 24);
+        for target in [1_000, 10_000].iter() {
+            let step = suggest_step_for_count(&vertices, &indices, *target);
+            let count = Voxels::voxelize(&vertices, &indices, step)
+                .grid_positions
+                .len();
+            let ratio = count as f64 / *target as f64;
+            assert!(ratio > 0.7 && ratio < 1.3);
+        }
+    }
+
+    #[test]
+    fn density_field_reports_full_half_and_empty_blocks() {
+        let mut cells = Vec::new();
+        for x in 0..4 {
+            for y in 0..2 {
+                // The first super-cell is full, the second holds only the bottom layer.
+                for z in 0..(if x < 2 { 2 } else { 1 }) {
+                    cells.push([x, y, z]);
+                }
+            }
+        }
+        // A lone cell past an empty super-cell, and one below the origin.
+        cells.push([6, 0, 0]);
+        cells.push([-1, 0, 0]);
+        let field = from_cells(cells).density_field([2, 2, 2]);
+        let mut expected = HashMap::new();
+        expected.insert([-1, 0, 0], 0.125);
+        expected.insert([0, 0, 0], 1.0);
+        expected.insert([1, 0, 0], 0.5);
+        expected.insert([2, 0, 0], 0.0);
+        expected.insert([3, 0, 0], 0.125);
+        assert_eq!(field, expected);
+    }
+}
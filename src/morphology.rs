@@ -0,0 +1,163 @@
+use super::components::{offset_pos, Connectivity};
+use super::voxelize::Voxels;
+use num_traits::Float;
+use std::collections::HashSet;
+
+impl<T: Float> Voxels<T> {
+    /// Grows the voxels by `radius` layers: each layer adds every empty cell adjacent to an occupied one
+    /// under `connectivity`. A `radius` of zero or less does nothing.
+    ///
+    /// Cells beyond the range of `i32` are never added.
+    pub fn dilate(&mut self, radius: i32, connectivity: Connectivity) {
+        let offsets = connectivity.offsets();
+        let mut front = self.grid_positions.iter().cloned().collect::<Vec<_>>();
+        for _ in 0..radius {
+            let mut grown = HashSet::new();
+            for pos in front.iter() {
+                for next in offsets
+                    .iter()
+                    .filter_map(|offset| offset_pos(*pos, *offset))
+                {
+                    if !self.grid_positions.contains(&next) {
+                        grown.insert(next);
+                    }
+                }
+            }
+            if grown.is_empty() {
+                break;
+            }
+            self.grid_positions.extend(grown.iter().cloned());
+            // Only the cells just added can have empty neighbors left.
+            front = grown.into_iter().collect();
+        }
+    }
+    /// Shrinks the voxels by `radius` layers: each layer removes every occupied cell with an empty neighbor
+    /// under `connectivity`. A `radius` of zero or less does nothing.
+    ///
+    /// `dilate` followed by `erode` with the same arguments closes pinholes and gaps narrower than the radius,
+    /// and `erode` followed by `dilate` removes specks and strands thinner than it.
+    pub fn erode(&mut self, radius: i32, connectivity: Connectivity) {
+        let offsets = connectivity.offsets();
+        for _ in 0..radius {
+            let exposed = self
+                .grid_positions
+                .iter()
+                .filter(|pos| {
+                    offsets
+                        .iter()
+                        .any(|offset| match offset_pos(**pos, *offset) {
+                            Some(next) => !self.grid_positions.contains(&next),
+                            None => true,
+                        })
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if exposed.is_empty() {
+                break;
+            }
+            for pos in exposed.iter() {
+                self.grid_positions.remove(pos);
+            }
+        }
+    }
+    /// Hollows out the voxels, keeping only the cells within `thickness` layers of an empty cell.
+    ///
+    /// This removes what `erode` with `Connectivity::TwentySix` would leave, so the wall is at least `thickness`
+    /// cells thick in every direction, including along diagonals. Use it after `fill`; a `thickness` of 1 keeps
+    /// just the surface shell, a `thickness` beyond half the width of the object keeps all of it, and a `thickness`
+    /// of zero or less keeps nothing.
+    pub fn shell(&mut self, thickness: i32) {
+        let mut core = Voxels {
+            grid_positions: self.grid_positions.clone(),
+            step: self.step,
+        };
+        core.erode(thickness, Connectivity::TwentySix);
+        for pos in core.grid_positions.iter() {
+            self.grid_positions.remove(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_cells<I: IntoIterator<Item = [i32; 3]>>(cells: I) -> Voxels<f64> {
+        Voxels {
+            grid_positions: cells.into_iter().collect(),
+            step: 0.1,
+        }
+    }
+
+    #[test]
+    fn dilating_a_single_voxel() {
+        let cases = [
+            (Connectivity::Six, 1, 7),
+            (Connectivity::Six, 2, 25),
+            (Connectivity::TwentySix, 1, 27),
+            (Connectivity::TwentySix, 2, 125),
+        ];
+        for (connectivity, radius, count) in cases.iter() {
+            let mut voxels = from_cells(vec![[3, -2, 7]]);
+            voxels.dilate(*radius, *connectivity);
+            assert_eq!(voxels.grid_positions.len(), *count);
+            let r = *radius;
+            assert_eq!(
+                voxels.min_max(),
+                ([3 - r, -2 - r, 7 - r], [3 + r, -2 + r, 7 + r])
+            );
+        }
+        let mut voxels = from_cells(vec![[0, 0, 0]]);
+        voxels.dilate(0, Connectivity::TwentySix);
+        assert_eq!(voxels.grid_positions.len(), 1);
+    }
+
+    #[test]
+    fn eroding_after_dilating_a_single_voxel_gives_it_back() {
+        for connectivity in [Connectivity::Six, Connectivity::TwentySix].iter() {
+            let mut voxels = from_cells(vec![[0, 0, 0]]);
+            voxels.dilate(1, *connectivity);
+            voxels.erode(1, *connectivity);
+            assert_eq!(
+                voxels.grid_positions,
+                from_cells(vec![[0, 0, 0]]).grid_positions
+            );
+        }
+        // Eroding further removes everything.
+        let mut voxels = from_cells(vec![[0, 0, 0]]);
+        voxels.dilate(2, Connectivity::TwentySix);
+        voxels.erode(3, Connectivity::TwentySix);
+        assert!(voxels.grid_positions.is_empty());
+    }
+
+    #[test]
+    fn dilating_stops_at_the_end_of_the_grid_range() {
+        let mut voxels = from_cells(vec![[i32::MAX, 0, 0]]);
+        voxels.dilate(2, Connectivity::Six);
+        // Two layers of an octahedron, without the six cells past `i32::MAX`.
+        assert_eq!(voxels.grid_positions.len(), 25 - 6);
+        assert_eq!(voxels.min_max().1[0], i32::MAX);
+    }
+
+    #[test]
+    fn shell_of_a_solid_block_is_the_hollow_block() {
+        let block = |n: i32, hollow: bool| {
+            from_cells(
+                (0..n * n * n)
+                    .map(|i| [i % n, i / n % n, i / n / n])
+                    .filter(|pos| !hollow || pos.iter().any(|c| *c == 0 || *c == n - 1)),
+            )
+        };
+        let mut voxels = block(5, false);
+        voxels.shell(1);
+        assert_eq!(voxels.grid_positions, block(5, true).grid_positions);
+        assert_eq!(voxels.grid_positions.len(), 125 - 27);
+
+        // Two layers leave only the center cell out, three keep everything, and zero keeps nothing.
+        for (thickness, count) in [(2, 124), (3, 125), (0, 0)].iter() {
+            let mut voxels = block(5, false);
+            voxels.shell(*thickness);
+            assert_eq!(voxels.grid_positions.len(), *count);
+        }
+    }
+}
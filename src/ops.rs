@@ -0,0 +1,141 @@
+use super::voxelize::{VoxelizeError, Voxels};
+use num_traits::Float;
+use std::collections::HashSet;
+
+impl<T: Float> Voxels<T> {
+    /// Merges all grids into one, reserving room for all their cells at once.
+    ///
+    /// Returns `VoxelizeError::StepMismatch` unless every step equals the first one exactly,
+    /// and `VoxelizeError::NoGrids` for an empty slice.
+    pub fn union_all(grids: &[Voxels<T>]) -> Result<Voxels<T>, VoxelizeError> {
+        let step = match grids.first() {
+            Some(first) => first.step,
+            None => return Err(VoxelizeError::NoGrids),
+        };
+        if let Some(index) = grids.iter().position(|grid| grid.step != step) {
+            return Err(VoxelizeError::StepMismatch { index });
+        }
+        let mut grid_positions =
+            HashSet::with_capacity(grids.iter().map(|grid| grid.grid_positions.len()).sum());
+        for grid in grids {
+            grid_positions.extend(grid.grid_positions.iter().cloned());
+        }
+        Ok(Voxels {
+            grid_positions,
+            step,
+        })
+    }
+    /// Returns the cells in `self`, `other` or both.
+    ///
+    /// Returns `VoxelizeError::StepMismatch` with `index` 1 unless both steps are exactly equal,
+    /// as are the other set operations below.
+    pub fn union(&self, other: &Voxels<T>) -> Result<Voxels<T>, VoxelizeError> {
+        self.check_same_step(other)?;
+        Ok(Voxels {
+            grid_positions: self
+                .grid_positions
+                .union(&other.grid_positions)
+                .cloned()
+                .collect(),
+            step: self.step,
+        })
+    }
+    /// Returns the cells in both `self` and `other`.
+    pub fn intersection(&self, other: &Voxels<T>) -> Result<Voxels<T>, VoxelizeError> {
+        self.check_same_step(other)?;
+        Ok(Voxels {
+            grid_positions: self
+                .grid_positions
+                .intersection(&other.grid_positions)
+                .cloned()
+                .collect(),
+            step: self.step,
+        })
+    }
+    /// Returns the cells in `self` but not in `other`, i.e. `self` with `other` carved out.
+    pub fn difference(&self, other: &Voxels<T>) -> Result<Voxels<T>, VoxelizeError> {
+        self.check_same_step(other)?;
+        Ok(Voxels {
+            grid_positions: self
+                .grid_positions
+                .difference(&other.grid_positions)
+                .cloned()
+                .collect(),
+            step: self.step,
+        })
+    }
+    fn check_same_step(&self, other: &Voxels<T>) -> Result<(), VoxelizeError> {
+        if self.step == other.step {
+            Ok(())
+        } else {
+            Err(VoxelizeError::StepMismatch { index: 1 })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_cells<I: IntoIterator<Item = [i32; 3]>>(cells: I, step: f64) -> Voxels<f64> {
+        Voxels {
+            grid_positions: cells.into_iter().collect(),
+            step,
+        }
+    }
+
+    #[test]
+    fn union_all_merges_overlapping_grids() {
+        let grids = [
+            from_cells((0..4).map(|x| [x, 0, 0]), 0.1),
+            from_cells((2..6).map(|x| [x, 0, 0]), 0.1),
+            from_cells(vec![[0, 1, 0]], 0.1),
+        ];
+        let merged = Voxels::union_all(&grids).unwrap();
+        assert_eq!(merged.step, 0.1);
+        assert_eq!(
+            merged.grid_positions,
+            (0..6).map(|x| [x, 0, 0]).chain(vec![[0, 1, 0]]).collect()
+        );
+        assert_eq!(
+            Voxels::union_all(&grids[..1]).unwrap().grid_positions,
+            grids[0].grid_positions
+        );
+    }
+
+    #[test]
+    fn union_all_rejects_no_grids_and_mixed_steps() {
+        assert_eq!(Voxels::<f64>::union_all(&[]), Err(VoxelizeError::NoGrids));
+        let grids = [
+            from_cells(vec![[0, 0, 0]], 0.1),
+            from_cells(vec![[1, 0, 0]], 0.1),
+            from_cells(vec![[2, 0, 0]], 0.2),
+        ];
+        assert_eq!(
+            Voxels::union_all(&grids),
+            Err(VoxelizeError::StepMismatch { index: 2 })
+        );
+    }
+
+    #[test]
+    fn set_operations_of_two_overlapping_rows() {
+        let a = from_cells((0..4).map(|x| [x, 0, 0]), 0.1);
+        let b = from_cells((2..6).map(|x| [x, 0, 0]), 0.1);
+        let row = |xs: std::ops::Range<i32>| xs.map(|x| [x, 0, 0]).collect::<HashSet<_>>();
+        assert_eq!(a.union(&b).unwrap().grid_positions, row(0..6));
+        assert_eq!(a.intersection(&b).unwrap().grid_positions, row(2..4));
+        assert_eq!(a.difference(&b).unwrap().grid_positions, row(0..2));
+        assert_eq!(b.difference(&a).unwrap().grid_positions, row(4..6));
+        assert_eq!(a.union(&b).unwrap().step, 0.1);
+    }
+
+    #[test]
+    fn set_operations_reject_a_different_step() {
+        let a = from_cells(vec![[0, 0, 0]], 0.1);
+        let b = from_cells(vec![[0, 0, 0]], 0.1 + 1e-12);
+        let mismatch = Err(VoxelizeError::StepMismatch { index: 1 });
+        assert_eq!(a.union(&b), mismatch);
+        assert_eq!(a.intersection(&b), mismatch);
+        assert_eq!(a.difference(&b), mismatch);
+    }
+}
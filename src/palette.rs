@@ -0,0 +1,46 @@
+/// Generates `n` visually distinct RGB colors.
+///
+/// Hues are spaced by the golden ratio conjugate, so any prefix of the list is spread evenly around the color wheel
+/// and adding colors never changes the earlier ones. Saturation and value are fixed.
+pub fn distinct_colors(n: usize) -> Vec<[u8; 3]> {
+    const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+    (0..n)
+        .map(|i| hsv_to_rgb((i as f64 * GOLDEN_RATIO_CONJUGATE).fract(), 0.65, 0.95))
+        .collect()
+}
+
+/// Converts a color with hue, saturation and value in `[0, 1]` to RGB.
+fn hsv_to_rgb(h: f64, s: f64, v: f64) -> [u8; 3] {
+    let h = h * 6.0;
+    let f = h - h.floor();
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+    let (r, g, b) = match h.floor() as i32 % 6 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+    let to_u8 = |c: f64| (c * 255.0).round() as u8;
+    [to_u8(r), to_u8(g), to_u8(b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_distinct_and_stable() {
+        let colors = distinct_colors(24);
+        for (i, a) in colors.iter().enumerate() {
+            for b in colors[..i].iter() {
+                assert_ne!(a, b);
+            }
+        }
+        assert_eq!(distinct_colors(5), colors[..5].to_vec());
+        assert!(distinct_colors(0).is_empty());
+    }
+}
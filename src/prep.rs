@@ -0,0 +1,117 @@
+use num_traits::Float;
+use std::collections::HashSet;
+
+/// Removes triangles occupying the same place as an earlier one, with the same or the reversed winding.
+///
+/// Triangles are compared by the exact positions of their corners, so duplicates that reference different but
+/// coincident vertices (as boolean modeling tools tend to export) are found too.
+/// Returns the remaining indices and the number of triangles removed.
+pub fn remove_duplicate_triangles<T: Float>(
+    vertices: &[[T; 3]],
+    indices: &[usize],
+) -> (Vec<usize>, usize) {
+    let key = |i: usize| {
+        let v = vertices[i];
+        [
+            v[0].integer_decode(),
+            v[1].integer_decode(),
+            v[2].integer_decode(),
+        ]
+    };
+    let mut seen = HashSet::new();
+    let mut kept = Vec::with_capacity(indices.len());
+    let mut removed = 0;
+    for index in indices.chunks(3) {
+        let mut corners = [key(index[0]), key(index[1]), key(index[2])];
+        corners.sort_unstable();
+        if seen.insert(corners) {
+            kept.extend_from_slice(index);
+        } else {
+            removed += 1;
+        }
+    }
+    (kept, removed)
+}
+
+/// Removes triangles whose index triple repeats an index, or whose corners coincide, such as `[0, 1, 1]`.
+///
+/// Such triangles have no area and usually come from careless index buffers. `Voxels::voxelize` skips them
+/// along with every other degenerate triangle, but they still count as triangles elsewhere, e.g. in `analyze_mesh`.
+/// Returns the remaining indices and the number of triangles removed.
+pub fn remove_repeated_vertex_triangles<T: Float>(
+    vertices: &[[T; 3]],
+    indices: &[usize],
+) -> (Vec<usize>, usize) {
+    let mut kept = Vec::with_capacity(indices.len());
+    let mut removed = 0;
+    for index in indices.chunks(3) {
+        let [a, b, c] = [vertices[index[0]], vertices[index[1]], vertices[index[2]]];
+        if a == b || b == c || c == a {
+            removed += 1;
+        } else {
+            kept.extend_from_slice(index);
+        }
+    }
+    (kept, removed)
+}
+
+/// Removes triangles referencing a vertex past the end of `vertices`, and a trailing incomplete triangle.
+///
+/// Lets a mostly valid mesh from a third-party exporter be voxelized instead of panicking on its bad triangles.
+/// Returns the remaining indices and the number of triangles removed.
+pub fn remove_out_of_range_triangles<T: Float>(
+    vertices: &[[T; 3]],
+    indices: &[usize],
+) -> (Vec<usize>, usize) {
+    let mut kept = Vec::with_capacity(indices.len());
+    let mut removed = 0;
+    for index in indices.chunks(3) {
+        if index.len() == 3 && index.iter().all(|i| *i < vertices.len()) {
+            kept.extend_from_slice(index);
+        } else {
+            removed += 1;
+        }
+    }
+    (kept, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::cube;
+    use crate::voxelize::{VoxelizeConfig, Voxels};
+
+    #[test]
+    fn duplicated_faces_are_removed_before_filling() {
+        let (mut vertices, indices) = cube(0.47);
+        // The top face again with the same winding, and the bottom face reversed through copies of its corners.
+        let mut doubled = indices.clone();
+        doubled.extend_from_slice(&indices[6..12]);
+        let copies = vertices.len();
+        vertices.extend_from_slice(&[vertices[0], vertices[1], vertices[2], vertices[3]]);
+        doubled.extend_from_slice(&[
+            copies,
+            copies + 1,
+            copies + 2,
+            copies + 1,
+            copies + 3,
+            copies + 2,
+        ]);
+
+        let (kept, removed) = remove_duplicate_triangles(&vertices, &doubled);
+        assert_eq!(removed, 4);
+        assert_eq!(kept, indices);
+
+        let config = VoxelizeConfig {
+            remove_duplicate_triangles: true,
+            ..VoxelizeConfig::default()
+        };
+        let mut deduplicated = Voxels::voxelize_with_config(&vertices, &doubled, 0.1, &config);
+        let mut clean = Voxels::voxelize(&vertices, &indices, 0.1);
+        assert_eq!(deduplicated, clean);
+        deduplicated.fill();
+        clean.fill();
+        assert_eq!(deduplicated, clean);
+        assert_eq!(clean.grid_positions.len(), 1000);
+    }
+}
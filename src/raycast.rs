@@ -0,0 +1,211 @@
+use super::voxelize::Voxels;
+use num_traits::Float;
+
+/// The first voxel hit by `Voxels::raycast`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit<T> {
+    /// Grid position of the voxel.
+    pub pos: [i32; 3],
+    /// Where the ray enters the voxel, in world space.
+    pub point: [T; 3],
+    /// Distance from the ray origin to `point`.
+    pub distance: T,
+    /// The face the ray enters through, in the order x plus, x minus, y plus, y minus, z plus, z minus
+    /// as in `face_counts`, or `None` if the ray starts inside the voxel.
+    pub face: Option<usize>,
+}
+
+impl<T: Float> Voxels<T> {
+    /// Finds the first voxel along the ray from `origin` towards `dir`, within `max_dist` of `origin`.
+    ///
+    /// `dir` need not be normalized; a zero or non-finite direction hits nothing. The ray is first clipped to
+    /// the bounding box of the voxels, then walks the cells it crosses one by one (Amanatides and Woo),
+    /// so the cost grows with the number of cells crossed inside the box, not with `max_dist`.
+    pub fn raycast(&self, origin: [T; 3], dir: [T; 3], max_dist: T) -> Option<RayHit<T>> {
+        let len = (dir[0] * dir[0] + dir[1] * dir[1] + dir[2] * dir[2]).sqrt();
+        if self.grid_positions.is_empty() || len == T::zero() || !len.is_finite() {
+            return None;
+        }
+        let dir = [dir[0] / len, dir[1] / len, dir[2] / len];
+        let step = self.step;
+        let (min, max) = self.min_max();
+
+        // Clips the ray to the bounding box, remembering the axis it enters through.
+        let mut t_enter = T::zero();
+        let mut t_exit = max_dist;
+        let mut enter_axis = None;
+        for axis in 0..3 {
+            let lo = T::from(min[axis]).unwrap() * step;
+            let hi = (T::from(max[axis]).unwrap() + T::one()) * step;
+            if dir[axis] == T::zero() {
+                if origin[axis] < lo || origin[axis] >= hi {
+                    return None;
+                }
+                continue;
+            }
+            let ta = (lo - origin[axis]) / dir[axis];
+            let tb = (hi - origin[axis]) / dir[axis];
+            let (near, far) = if ta < tb { (ta, tb) } else { (tb, ta) };
+            if near > t_enter {
+                t_enter = near;
+                enter_axis = Some(axis);
+            }
+            t_exit = t_exit.min(far);
+        }
+        if t_enter > t_exit {
+            return None;
+        }
+
+        let mut pos = [0; 3];
+        let mut t_next = [T::infinity(); 3];
+        let mut t_delta = [T::infinity(); 3];
+        for axis in 0..3 {
+            pos[axis] = if enter_axis == Some(axis) {
+                if dir[axis] > T::zero() {
+                    min[axis]
+                } else {
+                    max[axis]
+                }
+            } else {
+                let p = origin[axis] + dir[axis] * t_enter;
+                let cell = (p / step).floor().to_i64().unwrap_or(i64::from(min[axis]));
+                cell.max(i64::from(min[axis])).min(i64::from(max[axis])) as i32
+            };
+            if dir[axis] != T::zero() {
+                let boundary = if dir[axis] > T::zero() {
+                    T::from(pos[axis]).unwrap() + T::one()
+                } else {
+                    T::from(pos[axis]).unwrap()
+                } * step;
+                t_next[axis] = (boundary - origin[axis]) / dir[axis];
+                t_delta[axis] = step / dir[axis].abs();
+            }
+        }
+        let entered_face = |axis: usize| {
+            if dir[axis] > T::zero() {
+                2 * axis + 1
+            } else {
+                2 * axis
+            }
+        };
+
+        let mut t = t_enter;
+        let mut face = enter_axis.map(entered_face);
+        loop {
+            if self.grid_positions.contains(&pos) {
+                return Some(RayHit {
+                    pos,
+                    point: [
+                        origin[0] + dir[0] * t,
+                        origin[1] + dir[1] * t,
+                        origin[2] + dir[2] * t,
+                    ],
+                    distance: t,
+                    face,
+                });
+            }
+            let mut axis = 0;
+            for a in 1..3 {
+                if t_next[a] < t_next[axis] {
+                    axis = a;
+                }
+            }
+            t = t_next[axis];
+            if t > t_exit {
+                return None;
+            }
+            pos[axis] = pos[axis].checked_add(if dir[axis] > T::zero() { 1 } else { -1 })?;
+            t_next[axis] = t_next[axis] + t_delta[axis];
+            face = Some(entered_face(axis));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two cells of half a unit along x, with three empty cells between them.
+    fn two_cells() -> Voxels<f64> {
+        Voxels {
+            grid_positions: [[0, 0, 0], [4, 0, 0]].iter().cloned().collect(),
+            step: 0.5,
+        }
+    }
+
+    #[test]
+    fn axis_parallel_rays_hit_the_nearest_face() {
+        let voxels = two_cells();
+        let hit = voxels.raycast([-1.0, 0.25, 0.25], [2.0, 0.0, 0.0], 10.0);
+        assert_eq!(
+            hit,
+            Some(RayHit {
+                pos: [0, 0, 0],
+                point: [0.0, 0.25, 0.25],
+                distance: 1.0,
+                face: Some(1),
+            })
+        );
+        // From between the cells, towards either side.
+        let hit = voxels
+            .raycast([1.0, 0.25, 0.25], [1.0, 0.0, 0.0], 10.0)
+            .unwrap();
+        assert_eq!((hit.pos, hit.distance, hit.face), ([4, 0, 0], 1.0, Some(1)));
+        let hit = voxels
+            .raycast([3.0, 0.25, 0.25], [-1.0, 0.0, 0.0], 10.0)
+            .unwrap();
+        assert_eq!((hit.pos, hit.distance, hit.face), ([4, 0, 0], 0.5, Some(0)));
+        let hit = voxels
+            .raycast([0.25, 0.25, 3.0], [0.0, 0.0, -1.0], 10.0)
+            .unwrap();
+        assert_eq!((hit.pos, hit.distance, hit.face), ([0, 0, 0], 2.5, Some(4)));
+    }
+
+    #[test]
+    fn oblique_ray_and_ray_starting_inside() {
+        let voxels = two_cells();
+        let hit = voxels
+            .raycast([-1.0, -0.25, 0.25], [1.0, 0.5, 0.0], 10.0)
+            .unwrap();
+        assert_eq!((hit.pos, hit.face), ([0, 0, 0], Some(1)));
+        assert!((hit.distance - 1.25f64.sqrt()).abs() < 1e-12);
+        assert!((hit.point[0]).abs() < 1e-12 && (hit.point[1] - 0.25).abs() < 1e-12);
+
+        let hit = voxels
+            .raycast([2.25, 0.25, 0.25], [0.0, 1.0, 0.0], 10.0)
+            .unwrap();
+        assert_eq!((hit.pos, hit.distance, hit.face), ([4, 0, 0], 0.0, None));
+    }
+
+    #[test]
+    fn rays_passing_by_miss() {
+        let voxels = two_cells();
+        // Through the empty cells between the two voxels.
+        assert_eq!(
+            voxels.raycast([1.0, 0.25, 0.25], [0.0, 1.0, 0.0], 10.0),
+            None
+        );
+        // Beside the bounding box, parallel to it.
+        assert_eq!(
+            voxels.raycast([-1.0, 0.75, 0.25], [1.0, 0.0, 0.0], 10.0),
+            None
+        );
+        // Away from the voxels, short of them, and without a direction.
+        assert_eq!(
+            voxels.raycast([-1.0, 0.25, 0.25], [-1.0, 0.0, 0.0], 10.0),
+            None
+        );
+        assert_eq!(
+            voxels.raycast([-1.0, 0.25, 0.25], [1.0, 0.0, 0.0], 0.9),
+            None
+        );
+        assert_eq!(
+            voxels.raycast([-1.0, 0.25, 0.25], [0.0, 0.0, 0.0], 10.0),
+            None
+        );
+        assert_eq!(
+            voxels.raycast([-1.0, 0.25, 0.25], [f64::NAN, 0.0, 0.0], 10.0),
+            None
+        );
+    }
+}
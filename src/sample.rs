@@ -0,0 +1,87 @@
+use super::voxelize::{assert_input, build_triangles, CellAnchor, Voxels};
+use num_traits::Float;
+use std::collections::HashSet;
+
+/// Components of the R2 low-discrepancy sequence, based on the plastic number.
+const R2_ALPHA: [f64; 2] = [0.754_877_666_246_692_7, 0.569_840_290_998_053_2];
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes by sampling points on the surface and marking the cells containing them.
+    ///
+    /// Each triangle gets `area * samples_per_area` points, with the fractional parts carried over to the next
+    /// triangle so that the total is proportional to the surface area. Points are spread over a triangle with
+    /// the R2 low-discrepancy sequence, so the result is deterministic and evenly distributed.
+    /// Unlike `voxelize`, this does not guarantee a closed surface; cells only lightly touched may be missed.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the input `try_voxelize` rejects, except that an empty mesh gives no voxels.
+    pub fn voxelize_surface_sampled(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        step: T,
+        samples_per_area: T,
+    ) -> Self {
+        assert_input(vertices, indices, step);
+        let mut voxels = Voxels {
+            grid_positions: HashSet::new(),
+            step,
+        };
+        let two = T::one() + T::one();
+        let alpha = [T::from(R2_ALPHA[0]).unwrap(), T::from(R2_ALPHA[1]).unwrap()];
+        let mut carry = T::zero();
+        for tri in build_triangles(vertices, indices) {
+            let [p0, p1, p2] = tri.points;
+            let (e1, e2) = (p1 - p0, p2 - p0);
+            let n = e1.cross(&e2);
+            let samples = n.dot(&n).sqrt() / two * samples_per_area + carry;
+            let count = samples.floor();
+            carry = samples - count;
+            let mut i = T::zero();
+            while i < count {
+                i = i + T::one();
+                let mut u = (i * alpha[0]).fract();
+                let mut v = (i * alpha[1]).fract();
+                // Points of the unit square beyond the diagonal are folded back into the triangle.
+                if u + v > T::one() {
+                    u = T::one() - u;
+                    v = T::one() - v;
+                }
+                let p = p0 + e1 * u + e2 * v;
+                let cell = voxels.world_to_grid([p.x, p.y, p.z], CellAnchor::MinCorner);
+                voxels.grid_positions.insert(cell);
+            }
+        }
+        voxels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn larger_triangles_get_proportionally_more_cells() {
+        let small = vec![[0.0, 0.0, 0.5], [1.0, 0.0, 0.5], [0.0, 1.0, 0.5]];
+        let large = small
+            .iter()
+            .map(|v| [v[0] * 4.0, v[1] * 4.0, v[2]])
+            .collect::<Vec<_>>();
+        let cells = |vertices: &[[f64; 3]]| {
+            Voxels::voxelize_surface_sampled(vertices, &[0, 1, 2], 0.01, 100.0)
+                .grid_positions
+                .len()
+        };
+        // At this density the samples are far apart, so nearly every one gets its own cell.
+        let (small, large) = (cells(&small), cells(&large));
+        assert!((45..=50).contains(&small), "{}", small);
+        assert!((16 * 45..=16 * 50).contains(&large), "{}", large);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn voxelize_surface_sampled_rejects_an_index_out_of_bounds() {
+        let vertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        Voxels::voxelize_surface_sampled(&vertices, &[0, 1, 3], 0.1, 10.0);
+    }
+}
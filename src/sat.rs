@@ -4,11 +4,34 @@ use super::vector::Vector3;
 use super::voxelize::{Triangle, AABB};
 use num_traits::Float;
 
-pub(crate) fn triangle_aabb_intersects<T: Float>(triangle: &Triangle<T>, aabb: &AABB<T>) -> bool {
+/// Checks whether a triangle and a box overlap, by the separating axis theorem. Touching counts as overlapping.
+///
+/// This is the test `Voxels::voxelize` runs against every candidate cell, inflated by a small epsilon.
+///
+/// ```rust
+/// use meshvox::{triangle_aabb_intersects, Triangle, Vector3, AABB};
+///
+/// let triangle = Triangle::new(
+///     &Vector3::new(0.0, 0.0, 0.0),
+///     &Vector3::new(1.0, 0.0, 0.0),
+///     &Vector3::new(0.0, 1.0, 0.0),
+/// );
+/// let across = AABB {
+///     min: Vector3::new(0.2, 0.2, -0.1),
+///     max: Vector3::new(0.4, 0.4, 0.1),
+/// };
+/// // Inside the bounding box of the triangle, but beyond its hypotenuse.
+/// let beyond = AABB {
+///     min: Vector3::new(0.6, 0.6, -0.1),
+///     max: Vector3::new(0.9, 0.9, 0.1),
+/// };
+/// assert!(triangle_aabb_intersects(&triangle, &across));
+/// assert!(!triangle_aabb_intersects(&triangle, &beyond));
+/// ```
+pub fn triangle_aabb_intersects<T: Float>(triangle: &Triangle<T>, aabb: &AABB<T>) -> bool {
     // 3 axes tests
-    let aabb_aabb_sat = aabb_aabb_intersects(triangle, aabb);
-    if !aabb_aabb_sat {
-        return aabb_aabb_sat;
+    if !triangle.aabb.intersects(aabb) {
+        return false;
     }
 
     // 1 axis test
@@ -21,21 +44,6 @@ pub(crate) fn triangle_aabb_intersects<T: Float>(triangle: &Triangle<T>, aabb: &
     tri_edge_aabb_intersects(triangle, aabb)
 }
 
-#[inline]
-fn aabb_aabb_intersects<T: Float>(triangle: &Triangle<T>, aabb: &AABB<T>) -> bool {
-    let two = T::one() + T::one();
-    let a_c = (aabb.max + aabb.min) / two;
-    let a_h = (aabb.max - aabb.min) / two;
-    let b_c = (triangle.aabb.max + triangle.aabb.min) / two;
-    let b_h = (triangle.aabb.max - triangle.aabb.min) / two;
-    (a_c.x - a_h.x <= b_c.x + b_h.x)
-        && (b_c.x - b_h.x <= a_c.x + a_h.x)
-        && (a_c.y - a_h.y <= b_c.y + b_h.y)
-        && (b_c.y - b_h.y <= a_c.y + a_h.y)
-        && (a_c.z - a_h.z <= b_c.z + b_h.z)
-        && (b_c.z - b_h.z <= a_c.z + a_h.z)
-}
-
 #[inline]
 fn plane_aabb_intersects<T: Float>(triangle: &Triangle<T>, aabb: &AABB<T>) -> bool {
     let normal =
@@ -56,16 +64,16 @@ fn tri_edge_aabb_intersects<T: Float>(triangle: &Triangle<T>, aabb: &AABB<T>) ->
     let two = T::one() + T::one();
     let c = (aabb.max + aabb.min) / two;
     let h = (aabb.max - aabb.min) / two;
-    let v = vec![
+    let v = [
         triangle.points[0] - c,
         triangle.points[1] - c,
         triangle.points[2] - c,
     ];
-    let e = vec![Vector3::x_axis(), Vector3::y_axis(), Vector3::z_axis()];
-    let f = vec![v[1] - v[0], v[2] - v[1], v[0] - v[2]];
-    for i in 0..3 {
-        for j in 0..3 {
-            let a = e[i].cross(&f[j]);
+    let e = [Vector3::x_axis(), Vector3::y_axis(), Vector3::z_axis()];
+    let f = [v[1] - v[0], v[2] - v[1], v[0] - v[2]];
+    for e in e.iter() {
+        for f in f.iter() {
+            let a = e.cross(f);
             let p0 = a.dot(&(v[0]));
             let p1 = a.dot(&(v[1]));
             let p2 = a.dot(&(v[2]));
@@ -77,3 +85,109 @@ fn tri_edge_aabb_intersects<T: Float>(triangle: &Triangle<T>, aabb: &AABB<T>) ->
     }
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Triangles with corners in `[-1, 1)`, from a fixed linear congruential sequence.
+    fn random_triangles(count: usize) -> Vec<Triangle<f64>> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+        };
+        (0..count)
+            .map(|_| {
+                let mut p = || Vector3::new(next(), next(), next());
+                Triangle::new(&p(), &p(), &p())
+            })
+            .collect()
+    }
+
+    fn cube(min: [f64; 3], size: f64) -> AABB<f64> {
+        let min = Vector3::from(min);
+        AABB {
+            min,
+            max: min + Vector3::new(size, size, size),
+        }
+    }
+
+    #[test]
+    fn aabb_intersects_counts_touching_boxes() {
+        let a = cube([0.0, 0.0, 0.0], 1.0);
+        assert!(a.intersects(&cube([0.5, 0.5, 0.5], 1.0)));
+        assert!(a.intersects(&cube([1.0, 0.0, 0.0], 1.0)));
+        assert!(a.intersects(&cube([1.0, 1.0, 1.0], 1.0)));
+        assert!(!a.intersects(&cube([1.0 + 1e-9, 0.0, 0.0], 1.0)));
+        assert!(!a.intersects(&cube([0.0, -1.5, 0.0], 1.0)));
+    }
+
+    #[test]
+    fn quick_reject_keeps_every_box_holding_a_point_of_the_triangle() {
+        for (i, tri) in random_triangles(200).iter().enumerate() {
+            let [p0, p1, p2] = *tri.points();
+            for j in 0..20 {
+                // A point of the triangle, and a box of random size strictly around it, since rounding
+                // may separate a box that only touches the triangle.
+                let (u, v) = ((j % 5) as f64 / 5.0, (j / 5) as f64 / 5.0);
+                let p = p0 + (p1 - p0) * u + (p2 - p0) * (v * (1.0 - u));
+                let size = 0.001 * (1 + (i + j) % 50) as f64;
+                let offset = size * (1 + (i * 7 + j * 3) % 8) as f64 / 10.0;
+                let aabb = cube([p.x - offset, p.y - size + offset, p.z - size / 2.0], size);
+                assert!(tri.aabb().intersects(&aabb));
+                assert!(triangle_aabb_intersects(tri, &aabb));
+            }
+        }
+    }
+
+    #[test]
+    fn quick_reject_prunes_boxes_beside_the_triangle() {
+        for tri in random_triangles(50).iter() {
+            let max = tri.aabb().max;
+            let beside = cube([max.x + 0.01, 0.0, 0.0], 0.5);
+            assert!(!tri.aabb().intersects(&beside));
+            assert!(!triangle_aabb_intersects(tri, &beside));
+        }
+    }
+
+    #[test]
+    fn quick_reject_saves_full_tests_around_large_triangles() {
+        // The cells `Triangle::voxelize` may test: the grid range of each triangle, one cell past it on every side.
+        let step = 0.1;
+        let (mut candidates, mut full_tests, mut rejected_hits) = (0, 0, 0);
+        for tri in random_triangles(4).iter() {
+            let aabb = tri.aabb();
+            let first = [aabb.min.x, aabb.min.y, aabb.min.z].map(|c| (c / step).floor() as i32 - 1);
+            let last = [aabb.max.x, aabb.max.y, aabb.max.z].map(|c| (c / step).floor() as i32 + 1);
+            for x in first[0]..=last[0] {
+                for y in first[1]..=last[1] {
+                    for z in first[2]..=last[2] {
+                        let cell = cube([x, y, z].map(|c| f64::from(c) * step), step);
+                        let reaches = aabb.intersects(&cell);
+                        let plane_and_edges = plane_aabb_intersects(tri, &cell)
+                            && tri_edge_aabb_intersects(tri, &cell);
+                        candidates += 1;
+                        full_tests += usize::from(reaches);
+                        // Cells beyond the box of the triangle cannot touch it, so the plane and edge axes
+                        // alone accept some of them near its corners.
+                        rejected_hits += usize::from(!reaches && plane_and_edges);
+                        assert_eq!(
+                            triangle_aabb_intersects(tri, &cell),
+                            reaches && plane_and_edges
+                        );
+                    }
+                }
+            }
+        }
+        assert!(
+            full_tests * 10 < candidates * 9,
+            "{} of {}",
+            full_tests,
+            candidates
+        );
+        assert!(rejected_hits > 0);
+    }
+}
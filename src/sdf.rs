@@ -0,0 +1,153 @@
+use super::dense::{check_cells, dense_index};
+use super::voxelize::Voxels;
+use num_traits::Float;
+use std::convert::TryFrom;
+
+impl<T: Float> Voxels<T> {
+    /// Computes a narrow-band signed distance field over `min_max`, padded by `band` cells on every side.
+    ///
+    /// The surface is made of the occupied cells with an empty face neighbor, which get distance zero.
+    /// Every other cell gets the distance between its center and the nearest surface cell center, in world units,
+    /// negative if the cell is occupied and positive if it is empty. Distances follow a 3x3x3 chamfer transform
+    /// (steps of 1, √2 and √3 cells), which may overestimate the Euclidean distance by up to about 13%,
+    /// and are clamped to `band` cells. Call `fill` first to get negative distances inside a closed shell;
+    /// otherwise the shell is the whole surface and the cells it encloses count as outside.
+    ///
+    /// Returns the distances, laid out as `x + nx * (y + ny * z)`, their dimensions `[nx, ny, nz]`, and the grid
+    /// position of the cell at index 0, as in `to_dense`. An empty set gives an empty field with zero dimensions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the padded grid leaves the range of `i32` or holds more cells than `usize` can count.
+    pub fn to_sdf(&self, band: i32) -> (Vec<f32>, [usize; 3], [i32; 3]) {
+        if self.grid_positions.is_empty() {
+            return (Vec::new(), [0, 0, 0], [0, 0, 0]);
+        }
+        let band = band.max(0);
+        let (min, max) = self.min_max();
+        let mut origin = [0; 3];
+        let mut dims = [0usize; 3];
+        for axis in 0..3 {
+            origin[axis] = i32::try_from(i64::from(min[axis]) - i64::from(band))
+                .expect("padded grid should be within the range of i32");
+            dims[axis] =
+                (i64::from(max[axis]) - i64::from(min[axis]) + 1 + 2 * i64::from(band)) as usize;
+        }
+        if let Err(e) = check_cells(dims, usize::MAX) {
+            panic!("{}", e);
+        }
+        let [nx, ny, nz] = dims;
+        let len = nx * ny * nz;
+        let mut occupied = vec![false; len];
+        for pos in self.grid_positions.iter() {
+            occupied[dense_index(origin, dims, *pos).unwrap()] = true;
+        }
+        let neighbor = |x: usize, y: usize, z: usize, d: [isize; 3]| {
+            let (x, y, z) = (x as isize + d[0], y as isize + d[1], z as isize + d[2]);
+            if x < 0 || y < 0 || z < 0 || x >= nx as isize || y >= ny as isize || z >= nz as isize {
+                None
+            } else {
+                Some(x as usize + nx * (y as usize + ny * z as usize))
+            }
+        };
+
+        let limit = band as f32;
+        let mut distance = vec![limit; len];
+        for (i, d) in distance.iter_mut().enumerate() {
+            if !occupied[i] {
+                continue;
+            }
+            let (x, y, z) = (i % nx, i / nx % ny, i / nx / ny);
+            let exposed = [
+                [1, 0, 0],
+                [-1, 0, 0],
+                [0, 1, 0],
+                [0, -1, 0],
+                [0, 0, 1],
+                [0, 0, -1],
+            ]
+            .iter()
+            .any(|delta| match neighbor(x, y, z, *delta) {
+                Some(j) => !occupied[j],
+                None => true,
+            });
+            if exposed {
+                *d = 0.0;
+            }
+        }
+
+        // The neighbors visited before a cell in memory order, with the length of the step to each of them.
+        let mut before = Vec::with_capacity(13);
+        for dz in -1..2isize {
+            for dy in -1..2isize {
+                for dx in -1..2isize {
+                    if (dz, dy, dx) < (0, 0, 0) {
+                        let weight = ((dx * dx + dy * dy + dz * dz) as f32).sqrt();
+                        before.push(([dx, dy, dz], weight));
+                    }
+                }
+            }
+        }
+        // A forward sweep brings distances from the cells before, a backward sweep from the cells after.
+        for sign in [1, -1].iter() {
+            for k in 0..len {
+                let i = if *sign == 1 { k } else { len - 1 - k };
+                let (x, y, z) = (i % nx, i / nx % ny, i / nx / ny);
+                let mut d = distance[i];
+                for (delta, weight) in before.iter() {
+                    if let Some(j) = neighbor(x, y, z, delta.map(|c| c * sign)) {
+                        d = d.min(distance[j] + weight);
+                    }
+                }
+                distance[i] = d;
+            }
+        }
+
+        let step = self.step.to_f32().unwrap();
+        for (d, occupied) in distance.iter_mut().zip(occupied.iter()) {
+            *d = if *occupied && *d > 0.0 {
+                -*d * step
+            } else {
+                *d * step
+            };
+        }
+        (distance, dims, origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn filled_block_is_negative_inside_and_positive_outside() {
+        let mut grid_positions = HashSet::new();
+        for x in 0..5 {
+            for y in 0..5 {
+                for z in 0..5 {
+                    grid_positions.insert([x, y, z]);
+                }
+            }
+        }
+        let voxels = Voxels {
+            grid_positions,
+            step: 0.5,
+        };
+        let (sdf, dims, origin) = voxels.to_sdf(3);
+        assert_eq!((dims, origin), ([11, 11, 11], [-3, -3, -3]));
+        let at = |pos: [i32; 3]| sdf[dense_index(origin, dims, pos).unwrap()];
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+        // On the surface, two cells inside and two cells outside.
+        assert_eq!(at([0, 2, 2]), 0.0);
+        assert_eq!(at([4, 4, 4]), 0.0);
+        assert!(close(at([2, 2, 2]), -1.0));
+        assert!(close(at([-2, 2, 2]), 1.0));
+        // Diagonal steps, and the band clamping the far corner.
+        assert!(close(at([-1, -1, -1]), 3f32.sqrt() * 0.5));
+        assert!(close(at([-3, 2, 2]), 1.5));
+        assert!(close(at([-3, -3, -3]), 1.5));
+        assert_eq!(sdf.iter().filter(|d| **d < 0.0).count(), 27);
+        assert_eq!(sdf.iter().filter(|d| **d == 0.0).count(), 125 - 27);
+    }
+}
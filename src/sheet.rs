@@ -0,0 +1,197 @@
+use super::vector::Vector3;
+use super::voxelize::{assert_input, build_triangles, default_eps, CellAnchor, Voxels};
+use num_traits::Float;
+use std::collections::HashMap;
+
+/// Normals supplied to `Voxels::voxelize_sheet`.
+#[derive(Debug, Clone, Copy)]
+pub enum SheetNormals<'a, T> {
+    /// One normal per triangle.
+    PerTriangle(&'a [[T; 3]]),
+    /// One normal per vertex, averaged over the three corners of each triangle.
+    PerVertex(&'a [[T; 3]]),
+}
+
+/// The side of a sheet a voxel lies on, relative to the supplied normals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FacingSide {
+    /// The normal points towards the voxel.
+    Front,
+    /// The normal points away from the voxel.
+    Back,
+}
+
+/// Voxels of an open sheet together with the side of the sheet each one lies on.
+pub struct SheetVoxels<T: Float> {
+    /// The surface voxels.
+    pub voxels: Voxels<T>,
+    /// The facing side of every surface voxel.
+    pub sides: HashMap<[i32; 3], FacingSide>,
+}
+
+impl<T: Float> SheetVoxels<T> {
+    /// Gets the facing side of the voxel at `pos`, or `None` if it is not a surface voxel.
+    pub fn facing(&self, pos: [i32; 3]) -> Option<FacingSide> {
+        self.sides.get(&pos).cloned()
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes an open sheet and records on which side of it each voxel lies.
+    ///
+    /// A voxel is on the `Front` when its center is on the side the normal points to.
+    /// Where several triangles touch a voxel, their signed distances to its center are summed and the sign decides;
+    /// a center lying exactly on the sheet counts as `Front`.
+    /// The normals only pick the sides, so they need not be unit length nor match the winding.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the input `try_voxelize` rejects, except that an empty mesh gives no voxels.
+    /// Also panics if `SheetNormals::PerTriangle` holds fewer normals than there are triangles,
+    /// or if `SheetNormals::PerVertex` does not hold exactly one normal per vertex.
+    pub fn voxelize_sheet(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        normals: SheetNormals<T>,
+        step: T,
+    ) -> SheetVoxels<T> {
+        assert_input(vertices, indices, step);
+        if let SheetNormals::PerVertex(normals) = normals {
+            assert!(
+                normals.len() == vertices.len(),
+                "one normal per vertex is needed"
+            );
+        }
+        let tris = build_triangles(vertices, indices);
+        let to_vector = |n: [T; 3]| Vector3::new(n[0], n[1], n[2]);
+        let tri_normals = match normals {
+            SheetNormals::PerTriangle(normals) => {
+                assert!(
+                    normals.len() >= tris.len(),
+                    "one normal per triangle is needed"
+                );
+                normals[..tris.len()]
+                    .iter()
+                    .map(|n| to_vector(*n))
+                    .collect::<Vec<_>>()
+            }
+            SheetNormals::PerVertex(normals) => indices
+                .chunks(3)
+                .map(|index| {
+                    to_vector(normals[index[0]])
+                        + to_vector(normals[index[1]])
+                        + to_vector(normals[index[2]])
+                })
+                .collect(),
+        };
+
+        let eps = default_eps();
+        let mut distances: HashMap<[i32; 3], T> = HashMap::new();
+        let mut voxels = Voxels {
+            grid_positions: Default::default(),
+            step,
+        };
+        for (tri, normal) in tris.iter().zip(tri_normals) {
+            let len = normal.dot(&normal).sqrt();
+            let normal = if len > T::zero() {
+                normal / len
+            } else {
+                normal
+            };
+            for cell in tri.voxelize(step, eps) {
+                let c = voxels.grid_to_world(cell, CellAnchor::Center);
+                let distance = normal.dot(&(to_vector(c) - tri.points[0]));
+                let sum = distances.entry(cell).or_insert_with(T::zero);
+                *sum = *sum + distance;
+            }
+        }
+
+        let sides = distances
+            .iter()
+            .map(|(cell, distance)| {
+                let side = if *distance < T::zero() {
+                    FacingSide::Back
+                } else {
+                    FacingSide::Front
+                };
+                (*cell, side)
+            })
+            .collect::<HashMap<_, _>>();
+        voxels.grid_positions = sides.keys().cloned().collect();
+        SheetVoxels { voxels, sides }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A triangle in the plane z = 0.52, below the centers of the cells between z = 0.5 and 0.6.
+    fn triangle() -> Vec<[f64; 3]> {
+        vec![[0.02, 0.02, 0.52], [0.98, 0.02, 0.52], [0.02, 0.98, 0.52]]
+    }
+
+    #[test]
+    fn voxels_report_the_side_the_normal_points_to() {
+        let vertices = triangle();
+        let cases = [
+            (
+                SheetNormals::PerTriangle(&[[0.0, 0.0, 1.0]]),
+                FacingSide::Front,
+            ),
+            (
+                SheetNormals::PerTriangle(&[[0.0, 0.0, -2.0]]),
+                FacingSide::Back,
+            ),
+            (
+                SheetNormals::PerVertex(&[[0.0, 0.0, -1.0]; 3]),
+                FacingSide::Back,
+            ),
+        ];
+        for (normals, side) in cases.iter() {
+            let sheet = Voxels::voxelize_sheet(&vertices, &[0, 1, 2], *normals, 0.1);
+            // The sheet is one cell thick, and the centers of those cells lie above it.
+            assert!(!sheet.voxels.grid_positions.is_empty());
+            assert!(sheet.voxels.grid_positions.iter().all(|pos| pos[2] == 5));
+            assert!(sheet
+                .voxels
+                .grid_positions
+                .iter()
+                .all(|pos| sheet.facing(*pos) == Some(*side)));
+        }
+        assert_eq!(
+            Voxels::voxelize_sheet(
+                &vertices,
+                &[0, 1, 2],
+                SheetNormals::PerTriangle(&[[0.0, 0.0, 1.0]]),
+                0.1
+            )
+            .facing([50, 50, 50]),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "one normal per vertex")]
+    fn voxelize_sheet_rejects_missing_vertex_normals() {
+        let normals = [[0.0, 0.0, 1.0]; 2];
+        Voxels::voxelize_sheet(
+            &triangle(),
+            &[0, 1, 2],
+            SheetNormals::PerVertex(&normals),
+            0.1,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn voxelize_sheet_rejects_an_index_out_of_bounds() {
+        let normals = [[0.0, 0.0, 1.0]; 3];
+        Voxels::voxelize_sheet(
+            &triangle(),
+            &[0, 1, 3],
+            SheetNormals::PerVertex(&normals),
+            0.1,
+        );
+    }
+}
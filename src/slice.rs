@@ -0,0 +1,191 @@
+use super::voxelize::{Axis, Voxels};
+use num_traits::Float;
+use std::collections::{HashMap, HashSet};
+
+/// Gets the two in-plane axes of a slice perpendicular to `axis`, in ascending order.
+#[inline]
+pub(crate) fn plane_axes(axis: Axis) -> (usize, usize) {
+    match axis {
+        Axis::X => (1, 2),
+        Axis::Y => (0, 2),
+        Axis::Z => (0, 1),
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Projects the voxels along `axis` and gets the in-plane coordinates with at least one occupied cell.
+    ///
+    /// Coordinates are ordered as in `slice_contours`, e.g. `[x, y]` for `Axis::Z`.
+    pub fn footprint(&self, axis: Axis) -> HashSet<[i32; 2]> {
+        let (a, b) = plane_axes(axis);
+        self.grid_positions.iter().map(|p| [p[a], p[b]]).collect()
+    }
+    /// Extracts the closed outlines of the occupied region on the slice `index` perpendicular to `axis`.
+    ///
+    /// Each loop is a list of cell-corner coordinates `[u, v]` on the remaining axes in ascending order
+    /// (`[y, z]` for `Axis::X`, `[x, z]` for `Axis::Y`, `[x, y]` for `Axis::Z`), without repeating the first point
+    /// and without collinear intermediate points. Outer boundaries run counter-clockwise and holes clockwise.
+    /// Cells touching only at a corner belong to separate loops.
+    pub fn slice_contours(&self, axis: Axis, index: i32) -> Vec<Vec<[i32; 2]>> {
+        let c = axis.index();
+        let (a, b) = plane_axes(axis);
+        let pixels = self
+            .grid_positions
+            .iter()
+            .filter(|p| p[c] == index)
+            .map(|p| [p[a], p[b]])
+            .collect::<HashSet<_>>();
+
+        // Directed boundary edges with the occupied side on the left.
+        let mut edges: HashMap<[i32; 2], Vec<[i32; 2]>> = HashMap::new();
+        let mut add = |from: [i32; 2], to: [i32; 2]| edges.entry(from).or_default().push(to);
+        for &[u, v] in pixels.iter() {
+            if !pixels.contains(&[u, v - 1]) {
+                add([u, v], [u + 1, v]);
+            }
+            if !pixels.contains(&[u + 1, v]) {
+                add([u + 1, v], [u + 1, v + 1]);
+            }
+            if !pixels.contains(&[u, v + 1]) {
+                add([u + 1, v + 1], [u, v + 1]);
+            }
+            if !pixels.contains(&[u - 1, v]) {
+                add([u, v + 1], [u, v]);
+            }
+        }
+
+        let mut starts = edges.keys().cloned().collect::<Vec<_>>();
+        starts.sort_unstable();
+        let mut contours = Vec::new();
+        for start in starts {
+            while edges.get(&start).map_or(false, |e| !e.is_empty()) {
+                let mut contour = vec![start];
+                let mut from = start;
+                let mut to = edges.get_mut(&start).unwrap().pop().unwrap();
+                while to != start {
+                    let outgoing = edges.get_mut(&to).unwrap();
+                    let dir = [to[0] - from[0], to[1] - from[1]];
+                    // At a vertex shared by two diagonal cells, turn left to keep the cells apart.
+                    let left = [to[0] - dir[1], to[1] + dir[0]];
+                    let next = match outgoing.iter().position(|n| *n == left) {
+                        Some(i) => outgoing.swap_remove(i),
+                        None => outgoing.pop().unwrap(),
+                    };
+                    contour.push(to);
+                    from = to;
+                    to = next;
+                }
+                contours.push(remove_collinear(contour));
+            }
+        }
+        contours
+    }
+}
+
+fn remove_collinear(contour: Vec<[i32; 2]>) -> Vec<[i32; 2]> {
+    let n = contour.len();
+    (0..n)
+        .filter(|i| {
+            let prev = contour[(i + n - 1) % n];
+            let cur = contour[*i];
+            let next = contour[(i + 1) % n];
+            (cur[0] - prev[0]) * (next[1] - cur[1]) != (cur[1] - prev[1]) * (next[0] - cur[0])
+        })
+        .map(|i| contour[i])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The cells of layer `z = 3` whose centers lie between the radii `inner` and `outer`, plus a cell on another layer.
+    fn ring(inner: f64, outer: f64) -> Voxels<f64> {
+        let mut grid_positions = HashSet::new();
+        for x in -8..8 {
+            for y in -8..8 {
+                let r = ((x as f64 + 0.5).powi(2) + (y as f64 + 0.5).powi(2)).sqrt();
+                if r >= inner && r < outer {
+                    grid_positions.insert([x, y, 3]);
+                }
+            }
+        }
+        grid_positions.insert([0, 0, 4]);
+        Voxels {
+            grid_positions,
+            step: 0.1,
+        }
+    }
+
+    /// Twice the signed area of a loop, positive when it runs counter-clockwise.
+    fn doubled_area(contour: &[[i32; 2]]) -> i32 {
+        let n = contour.len();
+        (0..n)
+            .map(|i| {
+                let (p, q) = (contour[i], contour[(i + 1) % n]);
+                p[0] * q[1] - q[0] * p[1]
+            })
+            .sum()
+    }
+
+    #[test]
+    fn disk_has_one_counter_clockwise_loop() {
+        let disk = ring(0.0, 5.0);
+        let contours = disk.slice_contours(Axis::Z, 3);
+        assert_eq!(contours.len(), 1);
+        let cells = disk.grid_positions.iter().filter(|p| p[2] == 3).count() as i32;
+        assert_eq!(doubled_area(&contours[0]), 2 * cells);
+        assert!(disk.slice_contours(Axis::Z, 5).is_empty());
+    }
+
+    #[test]
+    fn annulus_has_an_outer_loop_and_a_hole() {
+        let annulus = ring(2.5, 6.0);
+        let mut areas = annulus
+            .slice_contours(Axis::Z, 3)
+            .iter()
+            .map(|contour| doubled_area(contour))
+            .collect::<Vec<_>>();
+        areas.sort_unstable();
+        assert_eq!(areas.len(), 2);
+        assert!(areas[0] < 0 && areas[1] > 0);
+        let cells = annulus.grid_positions.iter().filter(|p| p[2] == 3).count() as i32;
+        assert_eq!(areas[0] + areas[1], 2 * cells);
+    }
+
+    #[test]
+    fn diagonal_cells_get_separate_squares() {
+        let voxels = Voxels {
+            grid_positions: [[0, 0, 0], [0, 1, 1]].iter().cloned().collect(),
+            step: 0.1,
+        };
+        let mut contours = voxels.slice_contours(Axis::X, 0);
+        contours.sort();
+        assert_eq!(
+            contours,
+            vec![
+                vec![[0, 0], [1, 0], [1, 1], [0, 1]],
+                vec![[1, 1], [2, 1], [2, 2], [1, 2]],
+            ]
+        );
+    }
+
+    #[test]
+    fn footprint_of_a_flat_block_follows_the_axis() {
+        let mut grid_positions = HashSet::new();
+        for x in 0..2 {
+            for y in 0..2 {
+                grid_positions.insert([x, y, 0]);
+            }
+        }
+        let voxels = Voxels {
+            grid_positions,
+            step: 0.1,
+        };
+        let square = [[0, 0], [0, 1], [1, 0], [1, 1]].iter().cloned().collect();
+        assert_eq!(voxels.footprint(Axis::Z), square);
+        let edge = [[0, 0], [1, 0]].iter().cloned().collect();
+        assert_eq!(voxels.footprint(Axis::X), edge);
+        assert_eq!(voxels.footprint(Axis::Y), edge);
+    }
+}
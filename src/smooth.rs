@@ -0,0 +1,401 @@
+use super::vector::Vector3;
+use super::voxelize::{Voxels, CORNER_SIGNS, FACE_TRIANGLES};
+use num_traits::Float;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+// The cube is split into six tetrahedra sharing the diagonal from corner 0 to corner 6.
+const CUBE_CORNERS: [[i32; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [1, 1, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [1, 1, 1],
+    [0, 1, 1],
+];
+const TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 5, 1, 6],
+    [0, 1, 2, 6],
+    [0, 2, 3, 6],
+    [0, 3, 7, 6],
+    [0, 7, 4, 6],
+    [0, 4, 5, 6],
+];
+
+/// Occupancy sampled at cell centers over a padded box.
+struct Field<T> {
+    values: Vec<T>,
+    dims: [i32; 3],
+    origin: [i32; 3],
+}
+
+impl<T: Float> Field<T> {
+    #[inline]
+    fn get(&self, p: [i32; 3]) -> T {
+        let l = [
+            p[0] - self.origin[0],
+            p[1] - self.origin[1],
+            p[2] - self.origin[2],
+        ];
+        if (0..3).any(|i| l[i] < 0 || l[i] >= self.dims[i]) {
+            return T::zero();
+        }
+        let (nx, ny) = (self.dims[0] as usize, self.dims[1] as usize);
+        self.values[l[0] as usize + nx * (l[1] as usize + ny * l[2] as usize)]
+    }
+    #[inline]
+    fn gradient(&self, p: [i32; 3]) -> Vector3<T> {
+        let half = T::from(0.5).unwrap();
+        let d = |axis: usize| {
+            let mut a = p;
+            let mut b = p;
+            a[axis] += 1;
+            b[axis] -= 1;
+            (self.get(a) - self.get(b)) * half
+        };
+        Vector3::new(d(0), d(1), d(2))
+    }
+    /// Averages each sample with its neighbors within `radius` along every axis.
+    fn box_blur(&mut self, radius: i32) {
+        for axis in 0..3 {
+            let mut blurred = Vec::with_capacity(self.values.len());
+            let norm = T::from(2 * radius + 1).unwrap();
+            for z in 0..self.dims[2] {
+                for y in 0..self.dims[1] {
+                    for x in 0..self.dims[0] {
+                        let p = [x + self.origin[0], y + self.origin[1], z + self.origin[2]];
+                        let mut sum = T::zero();
+                        for r in -radius..(radius + 1) {
+                            let mut q = p;
+                            q[axis] += r;
+                            sum = sum + self.get(q);
+                        }
+                        blurred.push(sum / norm);
+                    }
+                }
+            }
+            self.values = blurred;
+        }
+    }
+}
+
+#[inline]
+fn normalize<T: Float>(v: Vector3<T>) -> Vector3<T> {
+    let len = v.dot(&v).sqrt();
+    if len > T::epsilon() {
+        v / len
+    } else {
+        v
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Extracts a smooth surface with per-vertex normals.
+    ///
+    /// The occupancy of each cell is sampled at the cell center, optionally box blurred over `blur_radius` cells,
+    /// and the 0.5 iso-surface is extracted with marching tetrahedra. Normals come from the gradient of that field
+    /// rather than from the faces, which gives smooth shading on organic shapes.
+    /// Returns vertices, normals (one per vertex) and triangle indices.
+    /// Triangles are wound clockwise seen from outside, like `vertices_indices`, so their right-hand normals
+    /// point inward while the returned normals point outward.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the box of the voxels padded by `blur_radius + 1` cells leaves the range of `i32`,
+    /// or holds more samples than `usize` can count.
+    pub fn surface_normals_smooth(
+        &self,
+        blur_radius: u32,
+    ) -> (Vec<[T; 3]>, Vec<[T; 3]>, Vec<usize>) {
+        if self.grid_positions.is_empty() {
+            return (Vec::new(), Vec::new(), Vec::new());
+        }
+        let too_large = "the padded box of the voxels is too large";
+        let radius = i32::try_from(blur_radius).expect(too_large);
+        let pad = i64::from(radius) + 1;
+        let (min, max) = self.min_max();
+        let mut origin = [0; 3];
+        let mut dims = [0; 3];
+        for axis in 0..3 {
+            origin[axis] = i32::try_from(i64::from(min[axis]) - pad).expect(too_large);
+            let last = i32::try_from(i64::from(max[axis]) + pad).expect(too_large);
+            dims[axis] =
+                i32::try_from(i64::from(last) - i64::from(origin[axis]) + 1).expect(too_large);
+        }
+        let samples = (dims[0] as usize)
+            .checked_mul(dims[1] as usize)
+            .and_then(|n| n.checked_mul(dims[2] as usize))
+            .expect(too_large);
+        let mut field = Field {
+            values: vec![T::zero(); samples],
+            dims,
+            origin,
+        };
+        for p in self.grid_positions.iter() {
+            let l = [p[0] - origin[0], p[1] - origin[1], p[2] - origin[2]];
+            let (nx, ny) = (dims[0] as usize, dims[1] as usize);
+            field.values[l[0] as usize + nx * (l[1] as usize + ny * l[2] as usize)] = T::one();
+        }
+        if radius > 0 {
+            field.box_blur(radius);
+        }
+
+        let iso = T::from(0.5).unwrap();
+        let half = T::from(0.5).unwrap();
+        let step = self.step;
+        let mut vertices = Vec::new();
+        let mut normals: Vec<Vector3<T>> = Vec::new();
+        let mut indices = Vec::new();
+        let mut edge_vertices: HashMap<([i32; 3], [i32; 3]), usize> = HashMap::new();
+        let mut edge_vertex = |a: [i32; 3], b: [i32; 3], field: &Field<T>| -> usize {
+            let key = if a < b { (a, b) } else { (b, a) };
+            *edge_vertices.entry(key).or_insert_with(|| {
+                let (fa, fb) = (field.get(key.0), field.get(key.1));
+                let t = (iso - fa) / (fb - fa);
+                let pa = Vector3::new(
+                    T::from(key.0[0]).unwrap() + half,
+                    T::from(key.0[1]).unwrap() + half,
+                    T::from(key.0[2]).unwrap() + half,
+                );
+                let pb = Vector3::new(
+                    T::from(key.1[0]).unwrap() + half,
+                    T::from(key.1[1]).unwrap() + half,
+                    T::from(key.1[2]).unwrap() + half,
+                );
+                let p = (pa + (pb - pa) * t) * step;
+                let ga = field.gradient(key.0);
+                let gb = field.gradient(key.1);
+                vertices.push([p.x, p.y, p.z]);
+                normals.push(normalize(-(ga + (gb - ga) * t)));
+                vertices.len() - 1
+            })
+        };
+        for x in origin[0]..(origin[0] + dims[0] - 1) {
+            for y in origin[1]..(origin[1] + dims[1] - 1) {
+                for z in origin[2]..(origin[2] + dims[2] - 1) {
+                    let corners = CUBE_CORNERS
+                        .iter()
+                        .map(|c| [x + c[0], y + c[1], z + c[2]])
+                        .collect::<Vec<_>>();
+                    for tet in TETRAHEDRA.iter() {
+                        let points = [
+                            corners[tet[0]],
+                            corners[tet[1]],
+                            corners[tet[2]],
+                            corners[tet[3]],
+                        ];
+                        let (inside, outside): (Vec<&[i32; 3]>, Vec<&[i32; 3]>) =
+                            points.iter().partition(|p| field.get(**p) > iso);
+                        let mut polygon = Vec::new();
+                        match inside.len() {
+                            1 => {
+                                for o in outside.iter() {
+                                    polygon.push(edge_vertex(*inside[0], **o, &field));
+                                }
+                            }
+                            3 => {
+                                for i in inside.iter() {
+                                    polygon.push(edge_vertex(**i, *outside[0], &field));
+                                }
+                            }
+                            2 => {
+                                polygon.push(edge_vertex(*inside[0], *outside[0], &field));
+                                polygon.push(edge_vertex(*inside[0], *outside[1], &field));
+                                polygon.push(edge_vertex(*inside[1], *outside[1], &field));
+                                polygon.push(edge_vertex(*inside[1], *outside[0], &field));
+                            }
+                            _ => {}
+                        }
+                        for i in 1..polygon.len().saturating_sub(1) {
+                            indices.push([polygon[0], polygon[i], polygon[i + 1]]);
+                        }
+                    }
+                }
+            }
+        }
+        // Orient every triangle so its face normal opposes the outward field normals, as in `vertices_indices`.
+        let to_vec = |v: [T; 3]| Vector3::new(v[0], v[1], v[2]);
+        let indices: Vec<usize> = indices
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let face = (to_vec(vertices[b]) - to_vec(vertices[a]))
+                    .cross(&(to_vec(vertices[c]) - to_vec(vertices[a])));
+                let smooth = normals[a] + normals[b] + normals[c];
+                if face.dot(&smooth) > T::zero() {
+                    vec![a, c, b]
+                } else {
+                    vec![a, b, c]
+                }
+            })
+            .collect();
+        // Flat regions of the field have no gradient; fall back to the adjacent face normals there.
+        let mut face_normals = vec![Vector3::new(T::zero(), T::zero(), T::zero()); normals.len()];
+        for tri in indices.chunks(3) {
+            // Outward, against the winding.
+            let face = (to_vec(vertices[tri[2]]) - to_vec(vertices[tri[0]]))
+                .cross(&(to_vec(vertices[tri[1]]) - to_vec(vertices[tri[0]])));
+            for i in tri {
+                face_normals[*i] = face_normals[*i] + face;
+            }
+        }
+        let normals = normals
+            .into_iter()
+            .zip(face_normals)
+            .map(|(n, f)| {
+                if n.dot(&n) > T::epsilon() {
+                    n
+                } else {
+                    normalize(f)
+                }
+            })
+            .map(|n| [n.x, n.y, n.z])
+            .collect();
+        (vertices, normals, indices)
+    }
+    /// Gets the exposed faces with crease-angle shading: flat faces, smooth where the implied surface bends gently.
+    ///
+    /// At every corner, a face's vertex normal averages the normals of the faces meeting there that differ
+    /// from its own by at most `crease_angle` radians. Voxel faces meet at right angles, so up to 90 degrees
+    /// every edge stays hard and the normals are per face, while above 90 degrees the edges and corners are
+    /// rounded. Corners sharing a position and a normal share a vertex.
+    /// Returns vertices, normals (one per vertex) and indices, wound like `vertices_indices`.
+    pub fn surface_mesh_with_crease_angle(
+        &self,
+        crease_angle: T,
+    ) -> (Vec<[T; 3]>, Vec<[T; 3]>, Vec<usize>) {
+        let face_normal = |dir: usize| {
+            let mut n = [T::zero(); 3];
+            n[dir / 2] = [T::one(), -T::one()][dir % 2];
+            Vector3::new(n[0], n[1], n[2])
+        };
+        // Corners lie on a lattice of half steps, so doubled coordinates are exact keys.
+        let doubled = |pos: &[i32; 3], corner: usize| {
+            let sign = CORNER_SIGNS[corner];
+            [
+                2 * pos[0] + sign[0],
+                2 * pos[1] + sign[1],
+                2 * pos[2] + sign[2],
+            ]
+        };
+        let faces = self
+            .grid_positions
+            .iter()
+            .map(|pos| (*pos, self.exposed_faces(pos)))
+            .collect::<Vec<_>>();
+        // The face directions meeting at every corner, as bits in the order of `FACE_TRIANGLES`.
+        let mut corner_dirs: HashMap<[i32; 3], u8> = HashMap::new();
+        for (pos, exposed) in faces.iter() {
+            for (dir, face) in FACE_TRIANGLES.iter().enumerate() {
+                if exposed[dir] {
+                    for corner in face.iter().flatten() {
+                        *corner_dirs.entry(doubled(pos, *corner)).or_insert(0) |= 1 << dir;
+                    }
+                }
+            }
+        }
+
+        let min_cos = crease_angle.cos();
+        let half = self.step / (T::one() + T::one());
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        let mut welded: HashMap<([i32; 3], u8), usize> = HashMap::new();
+        for (pos, exposed) in faces.iter() {
+            for (dir, face) in FACE_TRIANGLES.iter().enumerate() {
+                if !exposed[dir] {
+                    continue;
+                }
+                for corner in face.iter().flatten() {
+                    let key = doubled(pos, *corner);
+                    // The directions averaged at this corner; the face's own is always among them.
+                    let smoothed = (0..6)
+                        .filter(|other| corner_dirs[&key] & (1 << other) != 0)
+                        .filter(|other| face_normal(dir).dot(&face_normal(*other)) >= min_cos)
+                        .fold(1u8 << dir, |mask, other| mask | (1 << other));
+                    let index = *welded.entry((key, smoothed)).or_insert_with(|| {
+                        let sum = (0..6).filter(|other| smoothed & (1 << other) != 0).fold(
+                            Vector3::new(T::zero(), T::zero(), T::zero()),
+                            |sum, other| sum + face_normal(other),
+                        );
+                        // Opposite faces cancel out; keep the face normal then.
+                        let n = if sum.dot(&sum) > T::epsilon() {
+                            normalize(sum)
+                        } else {
+                            face_normal(dir)
+                        };
+                        vertices.push([
+                            T::from(key[0]).unwrap() * half,
+                            T::from(key[1]).unwrap() * half,
+                            T::from(key[2]).unwrap() * half,
+                        ]);
+                        normals.push([n.x, n.y, n.z]);
+                        vertices.len() - 1
+                    });
+                    indices.push(index);
+                }
+            }
+        }
+        (vertices, normals, indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::sphere;
+
+    #[test]
+    fn sphere_normals_point_radially() {
+        let (vertices, indices) = sphere(1.0, 48, 24);
+        let voxels = Voxels::voxelize_solid(&vertices, &indices, 0.1);
+        let (points, normals, indices) = voxels.surface_normals_smooth(1);
+        assert!(!indices.is_empty());
+        let radial = |p: [f64; 3]| normalize(Vector3::new(p[0], p[1], p[2]));
+        let dots = points
+            .iter()
+            .zip(normals.iter())
+            .map(|(p, n)| radial(*p).dot(&Vector3::new(n[0], n[1], n[2])))
+            .collect::<Vec<_>>();
+        let mean = dots.iter().sum::<f64>() / dots.len() as f64;
+        assert!(mean > 0.99, "{}", mean);
+        assert!(dots.iter().all(|d| *d > 0.9));
+        // Clockwise seen from outside: every right-hand face normal points inward.
+        for tri in indices.chunks(3) {
+            let p = |i: usize| Vector3::new(points[i][0], points[i][1], points[i][2]);
+            let face = (p(tri[1]) - p(tri[0])).cross(&(p(tri[2]) - p(tri[0])));
+            assert!(face.dot(&p(tri[0])) < 0.0);
+        }
+    }
+
+    #[test]
+    fn crease_angle_keeps_cube_edges_hard_up_to_90_degrees() {
+        let mut cube = Voxels::with_capacity(8, 1.0);
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    cube.grid_positions.insert([x, y, z]);
+                }
+            }
+        }
+        let axis_aligned = |n: &[f64; 3]| {
+            let mut sorted = n.map(f64::abs);
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            sorted == [0.0, 0.0, 1.0]
+        };
+        for degrees in [30.0f64, 90.0].iter() {
+            let (vertices, normals, indices) =
+                cube.surface_mesh_with_crease_angle(degrees.to_radians());
+            assert_eq!(indices.len(), 24 * 6);
+            assert_eq!(vertices.len(), normals.len());
+            assert!(normals.iter().all(axis_aligned), "{}", degrees);
+            // Every triangle's corners share its face normal.
+            for tri in indices.chunks(3) {
+                assert!(tri.iter().all(|i| normals[*i] == normals[tri[0]]));
+            }
+        }
+        let (_, rounded, _) = cube.surface_mesh_with_crease_angle(100f64.to_radians());
+        assert!(rounded.iter().any(|n| !axis_aligned(n)));
+    }
+}
@@ -0,0 +1,346 @@
+use super::components::{offset_pos, Connectivity};
+use super::dense::{border_cells, check_cells, padded_box, BitGrid};
+use super::voxelize::{build_triangles, check_input, default_eps, Triangle, VoxelizeError, Voxels};
+use num_traits::Float;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Which cells `Voxels::voxelize_solid_with_rule` counts as part of the solid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SolidRule {
+    /// Every cell the surface touches, plus the interior. The cells cover the whole solid, leaving no hole,
+    /// so the volume never falls below that of a closed mesh and overestimates it by about half a layer of
+    /// surface cells.
+    #[default]
+    AnyOverlap,
+    /// Every cell whose center is inside the mesh. Surface cells are kept or dropped by their center,
+    /// so the volume errors on both sides of the surface largely cancel out, but thin parts may get holes.
+    CenterInside,
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes a closed mesh as a solid. The result equals `voxelize` followed by `fill`.
+    ///
+    /// The surface cells go straight into a dense bit grid, and both the exterior flood and the parity scans of
+    /// `fill` run on that grid. This avoids the hash lookups of `fill` for every cell of the bounding volume,
+    /// which dominate the two-pass path on large grids.
+    pub fn voxelize_solid(vertices: &[[T; 3]], indices: &[usize], step: T) -> Self {
+        Voxels::voxelize_solid_with_rule(vertices, indices, step, SolidRule::AnyOverlap)
+    }
+    /// Voxelizes a closed mesh as a solid, choosing the cells by `rule`.
+    ///
+    /// `SolidRule::AnyOverlap` gives the result of `voxelize_solid`, which fills cavities too. `SolidRule::CenterInside`
+    /// keeps only the cells whose center a vertical ray test finds inside the mesh, which brings the volume close to
+    /// that of the mesh and leaves a cavity bounded by an inward-facing shell empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the input `try_voxelize` rejects, except that an empty mesh gives no voxels, and if the
+    /// bounding box of the surface holds more cells than `usize` can count.
+    pub fn voxelize_solid_with_rule(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        step: T,
+        rule: SolidRule,
+    ) -> Self {
+        match Voxels::try_voxelize_solid(vertices, indices, step, rule, usize::MAX) {
+            Ok(voxels) => voxels,
+            Err(VoxelizeError::EmptyMesh) => Voxels {
+                grid_positions: HashSet::new(),
+                step,
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+    /// Voxelizes like `voxelize_solid_with_rule`, returning the errors of `try_voxelize`.
+    ///
+    /// The fill runs on dense grids over the bounding box of the surface. When that box would exceed `max_cells`
+    /// cells, this returns `VoxelizeError::TooManyCells` instead of allocating, like `try_into_dense`.
+    pub fn try_voxelize_solid(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        step: T,
+        rule: SolidRule,
+        max_cells: usize,
+    ) -> Result<Self, VoxelizeError> {
+        check_input(vertices, indices, step)?;
+        let eps = default_eps();
+        let tris = build_triangles(vertices, indices);
+        let surface = tris
+            .iter()
+            .flat_map(|tri| tri.voxelize(step, eps))
+            .collect::<Vec<_>>();
+        if surface.is_empty() {
+            return Ok(Voxels {
+                grid_positions: HashSet::new(),
+                step,
+            });
+        }
+        let (mut min, mut max) = (surface[0], surface[0]);
+        for pos in surface.iter() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(pos[axis]);
+                max[axis] = max[axis].max(pos[axis]);
+            }
+        }
+        // Padded by one cell where possible so that the exterior flood can go around the surface.
+        let (origin, dims) = padded_box(min, max);
+        check_cells(dims, max_cells)?;
+        let mut occupied = BitGrid::with_buffer(dims, origin, Vec::new());
+        for pos in surface {
+            occupied.set(pos);
+        }
+
+        let mut exterior = BitGrid::with_buffer(dims, origin, Vec::new());
+        let mut queue = VecDeque::new();
+        for pos in border_cells(origin, dims) {
+            if !occupied.get(pos) && !exterior.get(pos) {
+                exterior.set(pos);
+                queue.push_back(pos);
+            }
+        }
+        while let Some(pos) = queue.pop_front() {
+            for offset in Connectivity::Six.offsets().iter() {
+                if let Some(next) = offset_pos(pos, *offset) {
+                    if occupied.index(next).is_some() && !exterior.get(next) && !occupied.get(next)
+                    {
+                        exterior.set(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        // Number of axes whose parity scan finds each cell inside, as in `fill_axes` with all three axes.
+        let mut inside_count = vec![0u8; occupied.len()];
+        for c in 0..3 {
+            let a = (c + 1) % 3;
+            let b = (c + 2) % 3;
+            for u in min[a]..=max[a] {
+                for v in min[b]..=max[b] {
+                    let mut pos = [0; 3];
+                    pos[a] = u;
+                    pos[b] = v;
+                    let mut inside = true;
+                    let mut w_pre = None;
+                    for w in min[c]..=max[c] {
+                        pos[c] = w;
+                        if !occupied.get(pos) {
+                            continue;
+                        }
+                        if let Some(w_pre) = w_pre {
+                            if i64::from(w) - i64::from(w_pre) > 1 {
+                                if inside {
+                                    let mut gap = pos;
+                                    for p in (w_pre + 1)..w {
+                                        gap[c] = p;
+                                        inside_count[occupied.index(gap).unwrap()] += 1;
+                                    }
+                                }
+                                inside = !inside;
+                            }
+                        }
+                        w_pre = Some(w);
+                    }
+                }
+            }
+        }
+
+        let mut grid_positions = HashSet::new();
+        for z in min[2]..=max[2] {
+            for y in min[1]..=max[1] {
+                for x in min[0]..=max[0] {
+                    let pos = [x, y, z];
+                    let i = occupied.index(pos).unwrap();
+                    if occupied.get(pos) || !exterior.get(pos) || inside_count[i] == 3 {
+                        grid_positions.insert(pos);
+                    }
+                }
+            }
+        }
+        if rule == SolidRule::CenterInside {
+            let crossings = column_crossings(&tris, step, min, max);
+            let half = T::from(0.5).unwrap();
+            // Every cell is tested, not only the surface cells, so that a cavity enclosed by an inner shell,
+            // which the flood and parity scans fill, is emptied again.
+            grid_positions.retain(|pos| {
+                // The center is inside when an odd number of crossings lie above it.
+                let center = (T::from(pos[2]).unwrap() + half) * step;
+                match crossings.get(&[pos[0], pos[1]]) {
+                    Some(zs) => (zs.len() - zs.partition_point(|z| *z <= center)) % 2 == 1,
+                    None => false,
+                }
+            });
+        }
+        Ok(Voxels {
+            grid_positions,
+            step,
+        })
+    }
+}
+
+/// Finds where a vertical ray through the center of each column within `min` and `max` crosses the triangles,
+/// as sorted heights.
+///
+/// A ray through a shared edge or vertex is counted for exactly one of the triangles there, by the top-left rule
+/// on the projection to the xy plane.
+fn column_crossings<T: Float>(
+    tris: &[Triangle<T>],
+    step: T,
+    min: [i32; 3],
+    max: [i32; 3],
+) -> HashMap<[i32; 2], Vec<T>> {
+    let half = T::from(0.5).unwrap();
+    let mut crossings: HashMap<[i32; 2], Vec<T>> = HashMap::new();
+    for tri in tris {
+        let [a, b, c] = tri.points;
+        let area = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+        if area == T::zero() {
+            continue;
+        }
+        // Counter-clockwise in the xy plane, so inside means every edge function is positive.
+        let corners = if area > T::zero() {
+            [a, b, c]
+        } else {
+            [a, c, b]
+        };
+        let area = area.abs();
+        let first = |v: T| (v / step - half).ceil().to_i32().unwrap();
+        let last = |v: T| (v / step - half).floor().to_i32().unwrap();
+        for x in first(tri.aabb.min.x).max(min[0])..(last(tri.aabb.max.x).min(max[0]) + 1) {
+            for y in first(tri.aabb.min.y).max(min[1])..(last(tri.aabb.max.y).min(max[1]) + 1) {
+                let px = (T::from(x).unwrap() + half) * step;
+                let py = (T::from(y).unwrap() + half) * step;
+                let mut weights = [T::zero(); 3];
+                let mut inside = true;
+                for i in 0..3 {
+                    let (p, q) = (corners[i], corners[(i + 1) % 3]);
+                    let w = (q.x - p.x) * (py - p.y) - (q.y - p.y) * (px - p.x);
+                    // Top-left rule: a point on an edge belongs to the triangle only for top or left edges.
+                    let top_left = (q.y == p.y && q.x < p.x) || q.y < p.y;
+                    if w < T::zero() || (w == T::zero() && !top_left) {
+                        inside = false;
+                        break;
+                    }
+                    weights[(i + 2) % 3] = w;
+                }
+                if inside {
+                    let z = (corners[0].z * weights[0]
+                        + corners[1].z * weights[1]
+                        + corners[2].z * weights[2])
+                        / area;
+                    crossings.entry([x, y]).or_default().push(z);
+                }
+            }
+        }
+    }
+    for zs in crossings.values_mut() {
+        zs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    }
+    crossings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::mesh_signed_volume;
+    use crate::testing::{cube, pyramid, sphere, torus};
+
+    #[test]
+    fn voxelize_solid_equals_voxelize_then_fill() {
+        for (vertices, indices) in [pyramid(), torus(1.0, 0.4, 24, 12)].iter() {
+            let mut filled = Voxels::voxelize(vertices, indices, 0.05);
+            filled.fill();
+            assert_eq!(
+                Voxels::voxelize_solid(vertices, indices, 0.05).grid_positions,
+                filled.grid_positions
+            );
+        }
+    }
+
+    #[test]
+    fn try_voxelize_solid_rejects_a_grid_over_max_cells() {
+        let (vertices, indices) = pyramid();
+        // The surface spans 41 x 41 x 21 cells at this step, padded by one cell on every side.
+        let cells = 43 * 43 * 23;
+        let solid = |max_cells| {
+            Voxels::try_voxelize_solid(&vertices, &indices, 0.05, SolidRule::AnyOverlap, max_cells)
+        };
+        assert!(solid(cells).is_ok());
+        assert_eq!(
+            solid(cells - 1).unwrap_err(),
+            VoxelizeError::TooManyCells {
+                max_cells: cells - 1
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn voxelize_solid_rejects_an_index_out_of_bounds() {
+        let (vertices, _) = pyramid();
+        Voxels::voxelize_solid(&vertices, &[0, 1, 7], 0.1);
+    }
+
+    /// A cube of half size 0.44 with a cavity of half size 0.27, bounded by an inward-facing cube.
+    fn nested_shells() -> (Vec<[f64; 3]>, Vec<usize>) {
+        let (mut vertices, mut indices) = cube(0.44);
+        let (inner, inner_indices) = cube(0.27);
+        let offset = vertices.len();
+        vertices.extend(inner);
+        for triangle in inner_indices.chunks(3) {
+            indices.extend_from_slice(&[
+                offset + triangle[0],
+                offset + triangle[2],
+                offset + triangle[1],
+            ]);
+        }
+        (vertices, indices)
+    }
+
+    #[test]
+    fn any_overlap_fills_nested_shells_including_the_cavity() {
+        let (vertices, indices) = nested_shells();
+        let solid =
+            Voxels::voxelize_solid_with_rule(&vertices, &indices, 0.1, SolidRule::AnyOverlap);
+        // The outer surface reaches into cells -5 to 4, and everything inside is filled.
+        assert_eq!(solid.grid_positions.len(), 1000);
+        assert!(solid.grid_positions.contains(&[0, 0, 0]));
+    }
+
+    #[test]
+    fn center_inside_keeps_the_cells_between_nested_shells() {
+        let (vertices, indices) = nested_shells();
+        let solid =
+            Voxels::voxelize_solid_with_rule(&vertices, &indices, 0.1, SolidRule::CenterInside);
+        // Centers from -0.35 to 0.35 lie inside the outer cube, those from -0.25 to 0.25 in the cavity.
+        let expected = (0..512)
+            .map(|i| [i % 8 - 4, i / 8 % 8 - 4, i / 64 - 4])
+            .filter(|pos| pos.iter().any(|c| *c < -3 || *c > 2))
+            .collect::<HashSet<_>>();
+        assert_eq!(expected.len(), 512 - 216);
+        assert_eq!(solid.grid_positions, expected);
+    }
+
+    #[test]
+    fn any_overlap_bounds_the_sphere_volume_and_center_inside_comes_close() {
+        let (vertices, indices) = sphere(1.0, 64, 32);
+        let mesh_volume = mesh_signed_volume(&vertices, &indices);
+        for step in [0.2, 0.1, 0.05].iter() {
+            let any =
+                Voxels::voxelize_solid_with_rule(&vertices, &indices, *step, SolidRule::AnyOverlap);
+            let center = Voxels::voxelize_solid_with_rule(
+                &vertices,
+                &indices,
+                *step,
+                SolidRule::CenterInside,
+            );
+            assert!(center.grid_positions.is_subset(&any.grid_positions));
+            assert!(any.volume() > mesh_volume);
+            assert!(
+                (center.volume() / mesh_volume - 1.0).abs() < 0.07,
+                "{}",
+                step
+            );
+        }
+    }
+}
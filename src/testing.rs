@@ -0,0 +1,87 @@
+//! Meshes shared by the unit tests.
+
+/// The pyramid from the crate documentation, with its apex at z = 1 and its square base at z = 0.
+pub(crate) fn pyramid() -> (Vec<[f64; 3]>, Vec<usize>) {
+    let vertices = vec![
+        [0.0, 0.0, 1.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [-1.0, 0.0, 0.0],
+        [0.0, -1.0, 0.0],
+    ];
+    let indices = vec![0, 2, 1, 0, 1, 4, 0, 4, 3, 0, 3, 2, 2, 3, 4, 2, 4, 1];
+    (vertices, indices)
+}
+
+/// A torus around the z axis with `n` segments around the ring and `m` around the tube, wound outward.
+pub(crate) fn torus(r_major: f64, r_minor: f64, n: usize, m: usize) -> (Vec<[f64; 3]>, Vec<usize>) {
+    let tau = std::f64::consts::PI * 2.0;
+    let mut vertices = Vec::new();
+    for i in 0..n {
+        for j in 0..m {
+            let u = i as f64 / n as f64 * tau;
+            let w = j as f64 / m as f64 * tau;
+            let r = r_major + r_minor * w.cos();
+            vertices.push([r * u.cos(), r * u.sin(), r_minor * w.sin()]);
+        }
+    }
+    let mut indices = Vec::new();
+    for i in 0..n {
+        for j in 0..m {
+            let a = i * m + j;
+            let b = ((i + 1) % n) * m + j;
+            let c = ((i + 1) % n) * m + (j + 1) % m;
+            let d = i * m + (j + 1) % m;
+            indices.extend_from_slice(&[a, b, c, a, c, d]);
+        }
+    }
+    (vertices, indices)
+}
+
+/// An axis-aligned cube from `-half` to `half` on every axis, wound outward.
+pub(crate) fn cube(half: f64) -> (Vec<[f64; 3]>, Vec<usize>) {
+    let mut vertices = Vec::new();
+    for i in 0..8 {
+        let corner = |bit: usize| if i & bit == 0 { -half } else { half };
+        vertices.push([corner(1), corner(2), corner(4)]);
+    }
+    let indices = vec![
+        0, 2, 1, 1, 2, 3, // z = -half
+        4, 5, 6, 5, 7, 6, // z = half
+        0, 1, 4, 1, 5, 4, // y = -half
+        2, 6, 3, 3, 6, 7, // y = half
+        0, 4, 2, 2, 4, 6, // x = -half
+        1, 3, 5, 3, 7, 5, // x = half
+    ];
+    (vertices, indices)
+}
+
+/// A UV sphere of radius `r` around the origin with `n` segments around the z axis and `m` from pole to pole,
+/// wound outward.
+pub(crate) fn sphere(r: f64, n: usize, m: usize) -> (Vec<[f64; 3]>, Vec<usize>) {
+    let pi = std::f64::consts::PI;
+    let mut vertices = vec![[0.0, 0.0, r], [0.0, 0.0, -r]];
+    for j in 1..m {
+        let theta = j as f64 / m as f64 * pi;
+        for i in 0..n {
+            let phi = i as f64 / n as f64 * pi * 2.0;
+            vertices.push([
+                r * theta.sin() * phi.cos(),
+                r * theta.sin() * phi.sin(),
+                r * theta.cos(),
+            ]);
+        }
+    }
+    let ring = |j: usize, i: usize| 2 + (j - 1) * n + i % n;
+    let mut indices = Vec::new();
+    for i in 0..n {
+        indices.extend_from_slice(&[0, ring(1, i), ring(1, i + 1)]);
+        indices.extend_from_slice(&[1, ring(m - 1, i + 1), ring(m - 1, i)]);
+        for j in 1..m - 1 {
+            let (a, b) = (ring(j, i), ring(j, i + 1));
+            let (c, d) = (ring(j + 1, i + 1), ring(j + 1, i));
+            indices.extend_from_slice(&[a, d, c, a, c, b]);
+        }
+    }
+    (vertices, indices)
+}
@@ -0,0 +1,477 @@
+use super::components::offset_pos;
+use super::voxelize::{Axis, VoxelizeError, Voxels};
+use num_traits::Float;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+#[inline]
+fn mul_mat_vec<T: Float>(m: &[[T; 3]; 3], v: [T; 3]) -> [T; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes a mesh in its own frame, then turns it onto the world grid by `times` quarter turns about `axis`
+    /// with `rotate90`.
+    ///
+    /// Quarter turns map cells exactly onto cells, so the result equals voxelizing the rotated mesh, up to rounding,
+    /// without resampling. Other rotations move cell centers off the lattice and need `rotate_arbitrary`,
+    /// which resamples like `resample_to` and generally changes the cell count.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `voxelize` does.
+    pub fn voxelize_rotated90(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        step: T,
+        axis: Axis,
+        times: u8,
+    ) -> Voxels<T>
+    where
+        T: Send + Sync,
+    {
+        let mut voxels = Voxels::voxelize(vertices, indices, step);
+        voxels.rotate90(axis, times);
+        voxels
+    }
+    /// Rotates the voxels about the world origin by an arbitrary rotation matrix.
+    ///
+    /// Rotated cells no longer line up with the grid, so the result is resampled:
+    /// a target cell is occupied when its center, rotated back into the source frame,
+    /// falls into an occupied source cell. The cell count generally changes.
+    /// `rot` must be orthonormal, since its transpose is used as the inverse.
+    /// For quarter turns, `rotate90` keeps every cell instead.
+    ///
+    /// Fails with `VoxelizeError::CoordinateOverflow` if the rotated bounds would leave the range of `i32`.
+    pub fn rotate_arbitrary(&self, rot: [[T; 3]; 3]) -> Result<Voxels<T>, VoxelizeError> {
+        self.resample(&rot, self.step)
+    }
+    /// Resamples the voxels onto a grid of another step.
+    ///
+    /// A target cell is occupied when its center falls into an occupied source cell, as in `rotate_arbitrary`.
+    /// Halving the step turns every cell into eight, while doubling it keeps one cell out of each block of eight
+    /// by the source cell holding its center.
+    ///
+    /// Fails with `VoxelizeError::CoordinateOverflow` if the bounds at `step` would leave the range of `i32`.
+    /// Panics if `step` is not positive.
+    pub fn resample_to(&self, step: T) -> Result<Voxels<T>, VoxelizeError> {
+        if step <= T::epsilon() {
+            panic!("step should be positive value");
+        }
+        let (zero, one) = (T::zero(), T::one());
+        self.resample(
+            &[[one, zero, zero], [zero, one, zero], [zero, zero, one]],
+            step,
+        )
+    }
+    /// Resamples the voxels rotated by the orthonormal `rot` onto a grid of `step`.
+    fn resample(&self, rot: &[[T; 3]; 3], step: T) -> Result<Voxels<T>, VoxelizeError> {
+        if self.grid_positions.is_empty() {
+            return Ok(Voxels {
+                grid_positions: HashSet::new(),
+                step,
+            });
+        }
+        let source_step = self.step;
+        let inv = [
+            [rot[0][0], rot[1][0], rot[2][0]],
+            [rot[0][1], rot[1][1], rot[2][1]],
+            [rot[0][2], rot[1][2], rot[2][2]],
+        ];
+        let (min, max) = self.min_max();
+        let mut grid_min = [i32::MAX; 3];
+        let mut grid_max = [i32::MIN; 3];
+        for i in 0..8 {
+            let cell = [
+                if i & 1 == 0 { min[0] } else { max[0] },
+                if i & 2 == 0 { min[1] } else { max[1] },
+                if i & 4 == 0 { min[2] } else { max[2] },
+            ];
+            let corner = [
+                T::from(i64::from(cell[0]) + i64::from(i & 1 != 0)).unwrap() * source_step,
+                T::from(i64::from(cell[1]) + i64::from(i & 2 != 0)).unwrap() * source_step,
+                T::from(i64::from(cell[2]) + i64::from(i & 4 != 0)).unwrap() * source_step,
+            ];
+            let rotated = mul_mat_vec(rot, corner);
+            for axis in 0..3 {
+                let (lower, upper) = match (
+                    (rotated[axis] / step).floor().to_i32(),
+                    (rotated[axis] / step).ceil().to_i32(),
+                ) {
+                    (Some(lower), Some(upper)) => (lower, upper),
+                    _ => return Err(VoxelizeError::CoordinateOverflow { pos: cell }),
+                };
+                grid_min[axis] = grid_min[axis].min(lower);
+                grid_max[axis] = grid_max[axis].max(upper);
+            }
+        }
+        let half = T::from(0.5).unwrap();
+        let mut grid_positions = HashSet::new();
+        for x in grid_min[0]..grid_max[0] {
+            for y in grid_min[1]..grid_max[1] {
+                for z in grid_min[2]..grid_max[2] {
+                    let center = [
+                        (T::from(x).unwrap() + half) * step,
+                        (T::from(y).unwrap() + half) * step,
+                        (T::from(z).unwrap() + half) * step,
+                    ];
+                    let source = mul_mat_vec(&inv, center);
+                    // A center beyond the source grid cannot fall into an occupied cell.
+                    let source = match (
+                        (source[0] / source_step).floor().to_i32(),
+                        (source[1] / source_step).floor().to_i32(),
+                        (source[2] / source_step).floor().to_i32(),
+                    ) {
+                        (Some(sx), Some(sy), Some(sz)) => [sx, sy, sz],
+                        _ => continue,
+                    };
+                    if self.grid_positions.contains(&source) {
+                        grid_positions.insert([x, y, z]);
+                    }
+                }
+            }
+        }
+        Ok(Voxels {
+            grid_positions,
+            step,
+        })
+    }
+    /// Scales the voxels about the grid origin by a separate factor per axis, keeping `step`.
+    ///
+    /// This resamples: every source cell is mapped to all target cells overlapped by its scaled extent,
+    /// so enlarging leaves no gaps and shrinking merges cells. The cell count generally changes.
+    /// Fails with `VoxelizeError::CoordinateOverflow` if a scaled position would leave the range of `i32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a factor is not a positive finite number.
+    pub fn scale_nonuniform(&self, factor: [T; 3]) -> Result<Voxels<T>, VoxelizeError> {
+        if factor.iter().any(|f| !f.is_finite() || *f <= T::zero()) {
+            panic!("factor should be positive finite value");
+        }
+        let mut grid_positions = HashSet::new();
+        for pos in self.grid_positions.iter() {
+            let overflow = || VoxelizeError::CoordinateOverflow { pos: *pos };
+            let mut first = [0; 3];
+            let mut last = [0; 3];
+            for axis in 0..3 {
+                let a = T::from(pos[axis]).unwrap() * factor[axis];
+                let b = T::from(i64::from(pos[axis]) + 1).unwrap() * factor[axis];
+                first[axis] = a.floor().to_i32().ok_or_else(overflow)?;
+                // The last overlapped cell ends at `ceil(b)`, which itself may be one past `i32::MAX`.
+                last[axis] = i32::try_from(b.ceil().to_i64().ok_or_else(overflow)? - 1)
+                    .map_err(|_| overflow())?
+                    .max(first[axis]);
+            }
+            for x in first[0]..=last[0] {
+                for y in first[1]..=last[1] {
+                    for z in first[2]..=last[2] {
+                        grid_positions.insert([x, y, z]);
+                    }
+                }
+            }
+        }
+        Ok(Voxels {
+            grid_positions,
+            step: self.step,
+        })
+    }
+    /// Moves the voxels by `offset` grid cells, keeping `step`.
+    ///
+    /// Fails with `VoxelizeError::CoordinateOverflow`, leaving the voxels unchanged, if any position would leave
+    /// the range of `i32`.
+    pub fn translate(&mut self, offset: [i32; 3]) -> Result<(), VoxelizeError> {
+        let grid_positions = self
+            .grid_positions
+            .iter()
+            .map(|pos| {
+                offset_pos(*pos, offset).ok_or(VoxelizeError::CoordinateOverflow { pos: *pos })
+            })
+            .collect::<Result<_, _>>()?;
+        self.grid_positions = grid_positions;
+        Ok(())
+    }
+    /// Rotates the voxels about `axis` through the world origin by `times` quarter turns, counterclockwise
+    /// looking down the axis from its positive side.
+    ///
+    /// Cells map exactly onto cells, so nothing is resampled and the cell count is kept. A turn takes the cell
+    /// spanning `[i, i + 1)` along one axis to `[-i - 1, -i)`, which always fits in `i32`.
+    pub fn rotate90(&mut self, axis: Axis, times: u8) {
+        let a = (axis.index() + 1) % 3;
+        let b = (axis.index() + 2) % 3;
+        let turns = times % 4;
+        if turns == 0 {
+            return;
+        }
+        self.grid_positions = self
+            .grid_positions
+            .iter()
+            .map(|pos| {
+                let mut rotated = *pos;
+                for _ in 0..turns {
+                    let (u, v) = (rotated[a], rotated[b]);
+                    rotated[a] = !v;
+                    rotated[b] = u;
+                }
+                rotated
+            })
+            .collect();
+    }
+    /// Mirrors the voxels across the world-space plane perpendicular to `axis` at `plane_world`.
+    ///
+    /// Cells land exactly on the grid only when the plane lies on a cell boundary or a cell center,
+    /// so the plane is rounded to the nearest multiple of half a step.
+    /// Fails with `VoxelizeError::GridOverflow` if the plane is NaN, infinite or beyond the grid range, and with
+    /// `VoxelizeError::CoordinateOverflow` if a mirrored position would leave the range of `i32`. The voxels are
+    /// left unchanged on failure.
+    pub fn mirror_world(&mut self, axis: Axis, plane_world: T) -> Result<(), VoxelizeError> {
+        let c = axis.index();
+        // Cell `i` spans `[i, i + 1)`, so across the plane `p` it spans `[2p - i - 1, 2p - i)`.
+        let twice_plane = (plane_world / self.step * (T::one() + T::one()))
+            .round()
+            .to_i64()
+            .ok_or_else(|| VoxelizeError::GridOverflow {
+                coordinate: plane_world.to_f64().unwrap_or(f64::NAN),
+                step: self.step.to_f64().unwrap_or(f64::NAN),
+            })?;
+        let grid_positions = self
+            .grid_positions
+            .iter()
+            .map(|pos| {
+                let mut mirrored = *pos;
+                mirrored[c] = i32::try_from(twice_plane - i64::from(pos[c]) - 1)
+                    .map_err(|_| VoxelizeError::CoordinateOverflow { pos: *pos })?;
+                Ok(mirrored)
+            })
+            .collect::<Result<_, _>>()?;
+        self.grid_positions = grid_positions;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::pyramid;
+
+    fn block(n: i32) -> Voxels<f64> {
+        let mut grid_positions = HashSet::new();
+        for x in 0..n {
+            for y in 0..n {
+                for z in 0..n {
+                    grid_positions.insert([x, y, z]);
+                }
+            }
+        }
+        Voxels {
+            grid_positions,
+            step: 0.1,
+        }
+    }
+
+    #[test]
+    fn cube_rotated_45_degrees_keeps_its_volume() {
+        let c = std::f64::consts::FRAC_1_SQRT_2;
+        let rot = [[c, -c, 0.0], [c, c, 0.0], [0.0, 0.0, 1.0]];
+        let rotated = block(10).rotate_arbitrary(rot).unwrap();
+        let count = rotated.grid_positions.len();
+        assert!((850..=1150).contains(&count), "{}", count);
+        let (min, max) = rotated.min_max();
+        assert_eq!((min[2], max[2]), (0, 9));
+        // The corners of the square now point along the axes, about 10 / sqrt(2) cells either side of x = 0.
+        assert!((-8..=-6).contains(&min[0]) && (6..=7).contains(&max[0]));
+        assert!(rotated.grid_positions.contains(&[0, 7, 5]));
+        assert!(!rotated.grid_positions.contains(&[6, 0, 5]));
+    }
+
+    #[test]
+    fn rotate_arbitrary_reports_overflow() {
+        let c = std::f64::consts::FRAC_1_SQRT_2;
+        let rot = [[c, -c, 0.0], [c, c, 0.0], [0.0, 0.0, 1.0]];
+        let far = Voxels {
+            grid_positions: [[i32::MAX - 1, i32::MAX - 1, 0]].iter().cloned().collect(),
+            step: 1.0,
+        };
+        assert!(matches!(
+            far.rotate_arbitrary(rot),
+            Err(VoxelizeError::CoordinateOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn resample_to_halves_and_doubles_the_step() {
+        let voxels = block(10);
+        let fine = voxels.resample_to(0.05).unwrap();
+        assert_eq!(fine.grid_positions.len(), 8000);
+        assert_eq!(fine.min_max(), ([0, 0, 0], [19, 19, 19]));
+        let coarse = voxels.resample_to(0.2).unwrap();
+        assert_eq!(coarse.grid_positions.len(), 125);
+        assert_eq!(coarse.step, 0.2);
+    }
+
+    #[test]
+    fn voxelize_rotated90_equals_voxelizing_the_rotated_mesh() {
+        // Moved off the cell boundaries, where the scan range of `voxelize` is not symmetric.
+        let (vertices, indices) = pyramid();
+        let vertices = vertices
+            .iter()
+            .map(|v| {
+                [
+                    v[0] * 0.93 + 0.013,
+                    v[1] * 0.93 + 0.017,
+                    v[2] * 0.93 + 0.011,
+                ]
+            })
+            .collect::<Vec<_>>();
+        let turned = vertices
+            .iter()
+            .map(|v| [-v[1], v[0], v[2]])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            Voxels::voxelize_rotated90(&vertices, &indices, 0.1, Axis::Z, 1).grid_positions,
+            Voxels::voxelize(&turned, &indices, 0.1).grid_positions
+        );
+    }
+
+    #[test]
+    fn scale_nonuniform_doubles_the_x_extent() {
+        let scaled = block(4).scale_nonuniform([2.0, 1.0, 1.0]).unwrap();
+        assert_eq!(scaled.grid_positions.len(), 128);
+        assert_eq!(scaled.min_max(), ([0, 0, 0], [7, 3, 3]));
+        assert_eq!(scaled.step, 0.1);
+        let halved = scaled.scale_nonuniform([0.5, 1.0, 1.0]).unwrap();
+        assert_eq!(halved.grid_positions, block(4).grid_positions);
+    }
+
+    #[test]
+    fn scale_nonuniform_reports_overflow() {
+        let far = Voxels {
+            grid_positions: [[i32::MAX, 0, 0]].iter().cloned().collect(),
+            step: 1.0,
+        };
+        assert_eq!(
+            far.scale_nonuniform([1.0, 1.0, 1.0])
+                .unwrap()
+                .grid_positions,
+            far.grid_positions
+        );
+        assert!(matches!(
+            far.scale_nonuniform([2.0, 1.0, 1.0]),
+            Err(VoxelizeError::CoordinateOverflow { .. })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "factor should be positive finite value")]
+    fn scale_nonuniform_rejects_a_nan_factor() {
+        let _ = block(2).scale_nonuniform([1.0, f64::NAN, 1.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "factor should be positive finite value")]
+    fn scale_nonuniform_rejects_a_negative_factor() {
+        let _ = block(2).scale_nonuniform([1.0, 1.0, -1.0]);
+    }
+
+    #[test]
+    fn mirroring_a_block_about_its_center_maps_it_onto_itself() {
+        let mut voxels = block(4);
+        // Through the boundary between cells 1 and 2.
+        voxels.mirror_world(Axis::X, 0.2).unwrap();
+        assert_eq!(voxels.grid_positions, block(4).grid_positions);
+        // Through the center of cell 1, moving the block one cell down.
+        voxels.mirror_world(Axis::Y, 0.15).unwrap();
+        assert_eq!(voxels.min_max(), ([0, -1, 0], [3, 2, 3]));
+        assert_eq!(voxels.grid_positions.len(), 64);
+    }
+
+    #[test]
+    fn mirroring_twice_restores_the_voxels() {
+        let mut voxels = Voxels::voxelize(&pyramid().0, &pyramid().1, 0.1);
+        let original = voxels.grid_positions.clone();
+        voxels.mirror_world(Axis::Z, 0.37).unwrap();
+        assert_ne!(voxels.grid_positions, original);
+        voxels.mirror_world(Axis::Z, 0.37).unwrap();
+        assert_eq!(voxels.grid_positions, original);
+    }
+
+    #[test]
+    fn mirror_world_rejects_a_non_finite_plane() {
+        let mut voxels = block(2);
+        for plane in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY].iter() {
+            assert!(matches!(
+                voxels.mirror_world(Axis::X, *plane),
+                Err(VoxelizeError::GridOverflow { .. })
+            ));
+        }
+        assert_eq!(voxels.grid_positions, block(2).grid_positions);
+    }
+
+    #[test]
+    fn translate_reports_overflow_near_the_end_of_the_range() {
+        let cells = [[0, i32::MAX - 1, 0], [0, 0, i32::MIN]];
+        let mut far = Voxels {
+            grid_positions: cells.iter().cloned().collect(),
+            step: 0.1,
+        };
+        assert_eq!(
+            far.translate([0, 2, 0]),
+            Err(VoxelizeError::CoordinateOverflow {
+                pos: [0, i32::MAX - 1, 0]
+            })
+        );
+        assert_eq!(
+            far.translate([0, 0, -1]),
+            Err(VoxelizeError::CoordinateOverflow {
+                pos: [0, 0, i32::MIN]
+            })
+        );
+        assert_eq!(far.grid_positions, cells.iter().cloned().collect());
+        far.translate([0, 1, 0]).unwrap();
+        assert_eq!(
+            far.grid_positions,
+            [[0, i32::MAX, 0], [0, 1, i32::MIN]]
+                .iter()
+                .cloned()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn translate_composes_additively() {
+        let mut once = block(3);
+        once.translate([5, -2, 7]).unwrap();
+        let mut twice = block(3);
+        twice.translate([2, -5, 3]).unwrap();
+        twice.translate([3, 3, 4]).unwrap();
+        assert_eq!(once.grid_positions, twice.grid_positions);
+        assert_eq!(once.min_max(), ([5, -2, 7], [7, 0, 9]));
+        assert_eq!(once.step, 0.1);
+        once.translate([-5, 2, -7]).unwrap();
+        assert_eq!(once.grid_positions, block(3).grid_positions);
+    }
+
+    #[test]
+    fn rotating_back_restores_the_voxels() {
+        let (vertices, indices) = pyramid();
+        // Moved off the axes, so that no quarter turn maps it onto itself.
+        let mut original = Voxels::voxelize(&vertices, &indices, 0.1);
+        original.translate([30, 10, 5]).unwrap();
+        for axis in [Axis::X, Axis::Y, Axis::Z].iter() {
+            for times in 0..6u8 {
+                let mut voxels = Voxels::new(&original.grid_positions, 0.1);
+                voxels.rotate90(*axis, times);
+                assert_eq!(voxels.grid_positions.len(), original.grid_positions.len());
+                if times % 4 != 0 {
+                    assert_ne!(voxels.grid_positions, original.grid_positions);
+                }
+                voxels.rotate90(*axis, 4 - times % 4);
+                assert_eq!(voxels.grid_positions, original.grid_positions);
+            }
+        }
+    }
+}
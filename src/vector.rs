@@ -1,50 +1,65 @@
-use num_traits::{One, Zero};
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use num_traits::{Float, One, Zero};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
-#[derive(Debug, Copy, PartialEq)]
-pub(crate) struct Vector3<T> {
+/// A 3D vector, used for points and directions by the triangle and box types.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vector3<T> {
     pub x: T,
     pub y: T,
     pub z: T,
 }
 
 impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + One + Copy> Vector3<T> {
+    /// Creates a vector from its components.
     #[inline]
-    pub(crate) fn new(x: T, y: T, z: T) -> Self {
+    pub fn new(x: T, y: T, z: T) -> Self {
         Self { x, y, z }
     }
+    /// Gets the dot product.
     #[inline]
-    pub(crate) fn dot(&self, other: &Self) -> T {
+    pub fn dot(&self, other: &Self) -> T {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
+    /// Gets the cross product, following the right-hand rule.
     #[inline]
-    pub(crate) fn cross(&self, rhs: &Self) -> Self {
+    pub fn cross(&self, rhs: &Self) -> Self {
         let x = self.y * rhs.z - self.z * rhs.y;
         let y = self.z * rhs.x - self.x * rhs.z;
         let z = self.x * rhs.y - self.y * rhs.x;
         Self::new(x, y, z)
     }
+    /// Gets the unit vector along x.
     #[inline]
-    pub(crate) fn x_axis() -> Self {
+    pub fn x_axis() -> Self {
         Self::new(T::one(), T::zero(), T::zero())
     }
+    /// Gets the unit vector along y.
     #[inline]
-    pub(crate) fn y_axis() -> Self {
+    pub fn y_axis() -> Self {
         Self::new(T::zero(), T::one(), T::zero())
     }
+    /// Gets the unit vector along z.
     #[inline]
-    pub(crate) fn z_axis() -> Self {
+    pub fn z_axis() -> Self {
         Self::new(T::zero(), T::zero(), T::one())
     }
 }
 
-impl<T: Copy> Clone for Vector3<T> {
+impl<T: Float> Vector3<T> {
+    /// Gets the Euclidean length.
     #[inline]
-    fn clone(&self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y,
-            z: self.z,
+    pub fn length(&self) -> T {
+        self.dot(self).sqrt()
+    }
+    /// Scales the vector to unit length, or returns `None` for a zero or non-finite vector,
+    /// such as the normal of a degenerate triangle.
+    #[inline]
+    pub fn normalize(&self) -> Option<Self> {
+        let length = self.length();
+        if length > T::zero() && length.is_finite() {
+            Some(*self / length)
+        } else {
+            None
         }
     }
 }
@@ -109,6 +124,42 @@ impl<T: Div<Output = T> + Copy> Div<T> for Vector3<T> {
     }
 }
 
+impl<T: AddAssign> AddAssign for Vector3<T> {
+    #[inline]
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+        self.z += other.z;
+    }
+}
+
+impl<T: SubAssign> SubAssign for Vector3<T> {
+    #[inline]
+    fn sub_assign(&mut self, other: Self) {
+        self.x -= other.x;
+        self.y -= other.y;
+        self.z -= other.z;
+    }
+}
+
+impl<T: MulAssign + Copy> MulAssign<T> for Vector3<T> {
+    #[inline]
+    fn mul_assign(&mut self, rhs: T) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
+    }
+}
+
+impl<T: DivAssign + Copy> DivAssign<T> for Vector3<T> {
+    #[inline]
+    fn div_assign(&mut self, rhs: T) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+    }
+}
+
 impl<T> From<(T, T, T)> for Vector3<T> {
     fn from(t: (T, T, T)) -> Self {
         Self {
@@ -119,8 +170,24 @@ impl<T> From<(T, T, T)> for Vector3<T> {
     }
 }
 
-impl<T> Into<(T, T, T)> for Vector3<T> {
-    fn into(self) -> (T, T, T) {
-        (self.x, self.y, self.z)
+impl<T> From<Vector3<T>> for (T, T, T) {
+    fn from(v: Vector3<T>) -> Self {
+        (v.x, v.y, v.z)
+    }
+}
+
+impl<T: Copy> From<[T; 3]> for Vector3<T> {
+    fn from(a: [T; 3]) -> Self {
+        Self {
+            x: a[0],
+            y: a[1],
+            z: a[2],
+        }
+    }
+}
+
+impl<T> From<Vector3<T>> for [T; 3] {
+    fn from(v: Vector3<T>) -> Self {
+        [v.x, v.y, v.z]
     }
 }
@@ -0,0 +1,85 @@
+//! A flat API over plain slices, exported to JavaScript with `wasm-bindgen`.
+
+use super::voxelize::{VoxelizeError, Voxels};
+use wasm_bindgen::prelude::*;
+
+/// Voxelizes a mesh given as flattened `[x, y, z, ...]` positions and returns flattened `[x, y, z, ...]` grid positions.
+///
+/// Grid positions are sorted so that the output is deterministic. Fails like `Voxels::try_voxelize`, and with
+/// `IncompleteVertex` when the number of positions is not a multiple of three. An empty mesh gives no positions.
+pub fn voxelize_flat(
+    positions: &[f32],
+    indices: &[u32],
+    step: f32,
+) -> Result<Vec<i32>, VoxelizeError> {
+    if positions.len() % 3 != 0 {
+        return Err(VoxelizeError::IncompleteVertex {
+            len: positions.len(),
+        });
+    }
+    let vertices = positions
+        .chunks_exact(3)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect::<Vec<_>>();
+    let indices = indices.iter().map(|i| *i as usize).collect::<Vec<_>>();
+    let voxels = match Voxels::try_voxelize(&vertices, &indices, step) {
+        Ok(voxels) => voxels,
+        Err(VoxelizeError::EmptyMesh) => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut grid_positions = voxels.grid_positions.into_iter().collect::<Vec<_>>();
+    grid_positions.sort_unstable();
+    Ok(grid_positions.into_iter().flatten().collect())
+}
+
+/// Exports `voxelize_flat` to JavaScript, where an error is thrown with its message instead of aborting the instance.
+#[wasm_bindgen(js_name = voxelize)]
+pub fn voxelize_wasm(positions: &[f32], indices: &[u32], step: f32) -> Result<Vec<i32>, JsValue> {
+    voxelize_flat(positions, indices, step).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voxelize_flat_matches_voxelize() {
+        let (vertices, indices) = crate::testing::pyramid();
+        let positions = vertices
+            .iter()
+            .flatten()
+            .map(|c| *c as f32)
+            .collect::<Vec<_>>();
+        let flat_indices = indices.iter().map(|i| *i as u32).collect::<Vec<_>>();
+        let flat = voxelize_flat(&positions, &flat_indices, 0.05).unwrap();
+
+        let vertices = vertices
+            .iter()
+            .map(|v| [v[0] as f32, v[1] as f32, v[2] as f32])
+            .collect::<Vec<_>>();
+        let mut expected = Voxels::voxelize(&vertices, &indices, 0.05)
+            .grid_positions
+            .into_iter()
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+        assert_eq!(flat, expected.into_iter().flatten().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn voxelize_flat_rejects_bad_input() {
+        let positions = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        assert_eq!(
+            voxelize_flat(&positions[..8], &[0, 1, 2], 0.1),
+            Err(VoxelizeError::IncompleteVertex { len: 8 })
+        );
+        assert_eq!(
+            voxelize_flat(&positions, &[0, 1, 3], 0.1),
+            Err(VoxelizeError::IndexOutOfBounds { index: 3, len: 3 })
+        );
+        assert_eq!(
+            voxelize_flat(&positions, &[0, 1, 2], 0.0),
+            Err(VoxelizeError::NonPositiveStep)
+        );
+        assert_eq!(voxelize_flat(&positions, &[], 0.1), Ok(Vec::new()));
+    }
+}
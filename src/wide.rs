@@ -0,0 +1,237 @@
+use super::voxelize::{VoxelizeError, Voxels};
+use num_traits::Float;
+use std::convert::TryFrom;
+
+/// Voxels whose grid positions are `i64`, as produced by `Voxels::voxelize_i64`, for meshes lying beyond the
+/// range of `i32` cells from the world origin, such as fine scans in a georeferenced frame.
+///
+/// The cells are kept relative to `origin`, so a model still spans at most the range of `i32` cells along each
+/// axis, far more than a set of voxels can hold in memory, while it may lie anywhere in the range of `i64`.
+pub struct Voxels64<T: Float> {
+    /// The cells relative to `origin`, with the step of the grid.
+    /// Cell `[i, j, k]` of `voxels` is cell `origin + [i, j, k]` of the world grid.
+    pub voxels: Voxels<T>,
+    /// The world grid position of cell `[0, 0, 0]` of `voxels`.
+    pub origin: [i64; 3],
+}
+
+impl<T: Float> Voxels64<T> {
+    /// Gets the world grid positions of the cells, in no particular order.
+    pub fn grid_positions(&self) -> impl Iterator<Item = [i64; 3]> + '_ {
+        self.voxels
+            .grid_positions
+            .iter()
+            .map(move |pos| self.to_world_grid(*pos))
+    }
+    /// Gets the number of cells.
+    pub fn len(&self) -> usize {
+        self.voxels.grid_positions.len()
+    }
+    /// Checks whether there are no cells.
+    pub fn is_empty(&self) -> bool {
+        self.voxels.grid_positions.is_empty()
+    }
+    /// Checks whether the cell at the world grid position `pos` is occupied.
+    pub fn contains(&self, pos: [i64; 3]) -> bool {
+        let mut local = [0; 3];
+        for axis in 0..3 {
+            match pos[axis]
+                .checked_sub(self.origin[axis])
+                .and_then(|d| i32::try_from(d).ok())
+            {
+                Some(d) => local[axis] = d,
+                None => return false,
+            }
+        }
+        self.voxels.grid_positions.contains(&local)
+    }
+    /// Gets the smallest and largest world grid position along each axis, or `None` if there are no voxels.
+    pub fn try_min_max(&self) -> Option<([i64; 3], [i64; 3])> {
+        let (min, max) = self.voxels.try_min_max()?;
+        Some((self.to_world_grid(min), self.to_world_grid(max)))
+    }
+    /// Fills the interior with voxels like `Voxels::fill`.
+    pub fn fill(&mut self) {
+        self.voxels.fill();
+    }
+    /// Gets the exposed faces like `Voxels::vertices_indices`, in world space.
+    pub fn vertices_indices(&self) -> (Vec<[T; 3]>, Vec<usize>) {
+        let (vertices, indices) = self.voxels.vertices_indices();
+        let offset = self.origin.map(|o| T::from(o).unwrap() * self.voxels.step);
+        (
+            vertices
+                .into_iter()
+                .map(|v| [v[0] + offset[0], v[1] + offset[1], v[2] + offset[2]])
+                .collect(),
+            indices,
+        )
+    }
+    /// Converts into voxels with `i32` grid positions relative to the world origin.
+    ///
+    /// Fails with `VoxelizeError::GridOverflow`, giving the world coordinate of the first axis out of range,
+    /// if a cell lies beyond the range of `i32`.
+    pub fn to_voxels(&self) -> Result<Voxels<T>, VoxelizeError> {
+        let step = self.voxels.step;
+        let grid_positions = self
+            .grid_positions()
+            .map(|pos| {
+                let mut narrow = [0; 3];
+                for axis in 0..3 {
+                    narrow[axis] =
+                        i32::try_from(pos[axis]).map_err(|_| VoxelizeError::GridOverflow {
+                            coordinate: pos[axis] as f64 * step.to_f64().unwrap_or(f64::NAN),
+                            step: step.to_f64().unwrap_or(f64::NAN),
+                        })?;
+                }
+                Ok(narrow)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Voxels {
+            grid_positions,
+            step,
+        })
+    }
+    #[inline]
+    fn to_world_grid(&self, pos: [i32; 3]) -> [i64; 3] {
+        [
+            self.origin[0] + i64::from(pos[0]),
+            self.origin[1] + i64::from(pos[1]),
+            self.origin[2] + i64::from(pos[2]),
+        ]
+    }
+}
+
+impl<T: Float> Voxels<T> {
+    /// Voxelizes the surface of a mesh like `voxelize`, on a grid of `i64` positions.
+    ///
+    /// The vertices are moved by a whole number of cells so that the lowest one lies in cell `[0, 0, 0]`,
+    /// voxelized with `voxelize`, and the cells are kept relative to that cell. The result equals voxelizing
+    /// the mesh in place up to rounding, which the move may change for coordinates far from the origin.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the input `try_voxelize_i64` rejects, except that an empty mesh gives no voxels.
+    pub fn voxelize_i64(vertices: &[[T; 3]], indices: &[usize], step: T) -> Voxels64<T>
+    where
+        T: Send + Sync,
+    {
+        match Voxels::try_voxelize_i64(vertices, indices, step) {
+            Ok(voxels) => voxels,
+            Err(VoxelizeError::EmptyMesh) => Voxels64 {
+                voxels: Voxels::with_capacity(0, step),
+                origin: [0; 3],
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+    /// Voxelizes like `voxelize_i64`, returning the errors of `try_voxelize` instead of panicking.
+    ///
+    /// The mesh must span at most the range of `i32` cells along each axis, and fails with
+    /// `VoxelizeError::GridOverflow` otherwise, or if its lowest vertex lies beyond the range of `i64` cells.
+    pub fn try_voxelize_i64(
+        vertices: &[[T; 3]],
+        indices: &[usize],
+        step: T,
+    ) -> Result<Voxels64<T>, VoxelizeError>
+    where
+        T: Send + Sync,
+    {
+        if step.is_nan() || step <= T::epsilon() {
+            return Err(VoxelizeError::NonPositiveStep);
+        }
+        // Non-finite coordinates are skipped here and reported by `try_voxelize` below.
+        let mut origin = [0; 3];
+        for axis in 0..3 {
+            let lowest = vertices
+                .iter()
+                .map(|v| v[axis])
+                .filter(|c| c.is_finite())
+                .fold(T::infinity(), T::min);
+            if lowest.is_finite() {
+                origin[axis] = (lowest / step).floor().to_i64().ok_or_else(|| {
+                    VoxelizeError::GridOverflow {
+                        coordinate: lowest.to_f64().unwrap_or(f64::NAN),
+                        step: step.to_f64().unwrap_or(f64::NAN),
+                    }
+                })?;
+            }
+        }
+        let offset = origin.map(|o| T::from(o).unwrap() * step);
+        let local = vertices
+            .iter()
+            .map(|v| [v[0] - offset[0], v[1] - offset[1], v[2] - offset[2]])
+            .collect::<Vec<_>>();
+        Ok(Voxels64 {
+            voxels: Voxels::try_voxelize(&local, indices, step)?,
+            origin,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::pyramid;
+
+    /// The pyramid moved by `shift` cells of `step` along every axis.
+    fn far_pyramid(shift: i64, step: f64) -> (Vec<[f64; 3]>, Vec<usize>) {
+        let (vertices, indices) = pyramid();
+        let offset = shift as f64 * step;
+        let vertices = vertices
+            .iter()
+            .map(|v| [v[0] + offset, v[1] + offset, v[2] + offset])
+            .collect();
+        (vertices, indices)
+    }
+
+    #[test]
+    fn voxelizes_a_model_beyond_the_range_of_i32() {
+        // Steps of a power of two keep the moved coordinates exact.
+        let step = 0.125;
+        let shift = 3_000_000_000i64;
+        let (vertices, indices) = far_pyramid(shift, step);
+        assert!(Voxels::try_voxelize(&vertices, &indices, step).is_err());
+
+        let mut far = Voxels::voxelize_i64(&vertices, &indices, step);
+        let mut near = Voxels::voxelize(&pyramid().0, &pyramid().1, step);
+        assert_eq!(far.len(), near.grid_positions.len());
+        let shifted = |pos: &[i32; 3]| [0, 1, 2].map(|axis| i64::from(pos[axis]) + shift);
+        assert!(near
+            .grid_positions
+            .iter()
+            .all(|pos| far.contains(shifted(pos))));
+        let (min, max) = near.min_max();
+        assert_eq!(far.try_min_max(), Some((shifted(&min), shifted(&max))));
+        assert!(matches!(
+            far.to_voxels(),
+            Err(VoxelizeError::GridOverflow { .. })
+        ));
+
+        far.fill();
+        near.fill();
+        assert_eq!(far.len(), near.grid_positions.len());
+        assert!(far
+            .grid_positions()
+            .all(|pos| pos.iter().all(|c| *c > i64::from(i32::MAX))));
+
+        let (vertices, indices) = far.vertices_indices();
+        assert_eq!(indices, near.vertices_indices().1);
+        let lowest = vertices.iter().map(|v| v[2]).fold(f64::INFINITY, f64::min);
+        assert_eq!(lowest, (shift as f64 - 0.5) * step);
+    }
+
+    #[test]
+    fn voxels_near_the_origin_convert_back() {
+        let (vertices, indices) = far_pyramid(-40, 0.125);
+        let wide = Voxels::voxelize_i64(&vertices, &indices, 0.125);
+        assert_eq!(
+            wide.to_voxels().unwrap().grid_positions,
+            Voxels::voxelize(&vertices, &indices, 0.125).grid_positions
+        );
+        assert!(Voxels::voxelize_i64(&vertices, &[], 0.125).is_empty());
+        assert!(matches!(
+            Voxels::try_voxelize_i64(&[[1e30, 0.0, 0.0]], &[0, 0, 0], 0.125),
+            Err(VoxelizeError::GridOverflow { .. })
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+//! Fixtures and golden files shared by the integration tests and the benches.
+
+#![allow(dead_code)]
+
+use meshvox::Voxels;
+use std::fs;
+use std::path::PathBuf;
+
+fn path(dir: &str, file: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", dir, file]
+        .iter()
+        .collect()
+}
+
+/// Loads the vertices and flat triangle indices of `tests/fixtures/<name>.obj`.
+///
+/// Only `v` and triangular `f` lines are read; face corners may carry `/vt/vn` suffixes, which are ignored.
+pub fn load_obj(name: &str) -> (Vec<[f64; 3]>, Vec<usize>) {
+    let file = path("fixtures", &format!("{}.obj", name));
+    let text = fs::read_to_string(&file)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", file.display(), e));
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                let mut v = [0.0; 3];
+                for c in v.iter_mut() {
+                    *c = words.next().unwrap().parse().unwrap();
+                }
+                vertices.push(v);
+            }
+            Some("f") => {
+                for corner in words {
+                    let index: usize = corner.split('/').next().unwrap().parse().unwrap();
+                    indices.push(index - 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    (vertices, indices)
+}
+
+/// Gets the cell count and the FNV-1a hash of the little-endian bytes of the sorted grid positions.
+pub fn fingerprint(voxels: &Voxels<f64>) -> (usize, u64) {
+    let mut positions = voxels.grid_positions.iter().cloned().collect::<Vec<_>>();
+    positions.sort_unstable();
+    let hash = positions
+        .iter()
+        .flat_map(|pos| pos.iter().flat_map(|c| c.to_le_bytes()))
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3)
+        });
+    (positions.len(), hash)
+}
+
+/// Compares the fingerprint of `voxels` with `tests/golden/<name>.txt`.
+///
+/// Run the tests with `MESHVOX_BLESS=1` to write the golden files instead, after a change that is meant
+/// to alter the output, and commit them with that change.
+pub fn check_golden(name: &str, voxels: &Voxels<f64>) {
+    let file = path("golden", &format!("{}.txt", name));
+    let (len, hash) = fingerprint(voxels);
+    let actual = format!("{} {:016x}\n", len, hash);
+    if std::env::var_os("MESHVOX_BLESS").is_some() {
+        fs::write(&file, &actual)
+            .unwrap_or_else(|e| panic!("cannot write {}: {}", file.display(), e));
+        return;
+    }
+    let expected = fs::read_to_string(&file)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", file.display(), e));
+    assert_eq!(
+        actual,
+        expected,
+        "{} no longer matches its golden file; rerun with MESHVOX_BLESS=1 if the change is intended",
+        name
+    );
+}
@@ -0,0 +1,44 @@
+//! Pins the voxels of the fixture meshes, so that any change to the cells produced, such as nondeterminism
+//! from parallel scanning, shows up as a failure. See `common::check_golden` for regenerating the files.
+
+mod common;
+
+use common::{check_golden, load_obj};
+use meshvox::Voxels;
+
+#[test]
+fn pyramid_surface() {
+    let (vertices, indices) = load_obj("pyramid");
+    check_golden("pyramid", &Voxels::voxelize(&vertices, &indices, 0.05));
+}
+
+#[test]
+fn pyramid_solid() {
+    let (vertices, indices) = load_obj("pyramid");
+    let mut voxels = Voxels::voxelize(&vertices, &indices, 0.05);
+    voxels.fill();
+    check_golden("pyramid_solid", &voxels);
+}
+
+#[test]
+fn torus_surface() {
+    let (vertices, indices) = load_obj("torus");
+    check_golden("torus", &Voxels::voxelize(&vertices, &indices, 0.05));
+}
+
+#[test]
+fn torus_solid() {
+    let (vertices, indices) = load_obj("torus");
+    let mut voxels = Voxels::voxelize(&vertices, &indices, 0.05);
+    voxels.fill();
+    check_golden("torus_solid", &voxels);
+}
+
+#[test]
+fn repeated_runs_match() {
+    let (vertices, indices) = load_obj("torus");
+    let first = Voxels::voxelize(&vertices, &indices, 0.05);
+    for _ in 0..3 {
+        assert_eq!(Voxels::voxelize(&vertices, &indices, 0.05), first);
+    }
+}